@@ -1,19 +1,300 @@
+//! Tick-based simulator of packets moving through a network.
+//!
+//! A [`Ntwk`] describes the nodes and links, a [`Sim`] the instructions to
+//! run over it, and a [`Simulator`] runs one over the other.
+
+use std::{fmt::Display, io};
+
 mod timeline;
 
+pub use timeline::{
+    AckOutcome, AckReport, Analysis, ArrivalProcess, Delivery, DropReason, Hop, Instr, MsgKind,
+    MsgReport, MsgStatus, NodeStats, Ntwk, NtwkBuilder, QueueDelays, RunResult, Sim, SimBuilder,
+    SimProblem, Simulator, Topology, Workload,
+};
+
+/// Everything that can stop the simulator before the end of a run, and
+/// the exit status it ends the process with:
+///
+/// | status | error                                   |
+/// |-------:|-----------------------------------------|
+/// |      0 | none, or help or version shown          |
+/// |      2 | `NoSwitchSpecified`: stray argument     |
+/// |      3 | `UnknownSwitch`                         |
+/// |      4 | `SwitchHasNoArg`                        |
+/// |      5 | `NtwkFileParseFailed`                   |
+/// |      6 | `SimFileParseFailed`                    |
+/// |      7 | `BadSwitchArg`                          |
+/// |      8 | `TraceFileFailed`                       |
+/// |      9 | `MetricsFileFailed`                     |
+/// |     10 | `DotFileFailed`                         |
+/// |     11 | `SnapshotFailed`: unreadable or unsaved |
+/// |     12 | `BadScenario`: builder or generator     |
+/// |     13 | `OutputFailed`: stdout can't be written |
+#[derive(Debug)]
 pub enum ErrCode {
     Okay,
     ShowHelpSign,
-    NoSwitchSpecified,
-    UnknownSwitch,
-    SwitchHasNoArg,
-    NtwkFileParseFailed,
-    SimFileParseFailed,
+    /// An argument that doesn't belong to any switch.
+    NoSwitchSpecified(String),
+    UnknownSwitch(String),
+    SwitchHasNoArg(char),
+    NtwkFileParseFailed {
+        file: String,
+        reason: String,
+    },
+    SimFileParseFailed {
+        file: String,
+        reason: String,
+    },
+    BadSwitchArg(char, String),
+    TraceFileFailed {
+        file: String,
+        source: io::Error,
+    },
+    /// Writing the CSV files with the given prefix failed.
+    MetricsFileFailed {
+        prefix: String,
+        source: io::Error,
+    },
+    DotFileFailed {
+        file: String,
+        source: io::Error,
+    },
+    SnapshotFailed {
+        file: String,
+        reason: String,
+    },
+    ShowVersionSign,
+    /// A network or sim put together in code that doesn't hold up.
+    BadScenario(String),
+    /// Writing generated files or an analysis to stdout failed.
+    OutputFailed(io::Error),
 }
 
+impl ErrCode {
+    /// Status to exit the process with, per the table above.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrCode::Okay | ErrCode::ShowHelpSign | ErrCode::ShowVersionSign => 0,
+            ErrCode::NoSwitchSpecified(_) => 2,
+            ErrCode::UnknownSwitch(_) => 3,
+            ErrCode::SwitchHasNoArg(_) => 4,
+            ErrCode::NtwkFileParseFailed { .. } => 5,
+            ErrCode::SimFileParseFailed { .. } => 6,
+            ErrCode::BadSwitchArg(..) => 7,
+            ErrCode::TraceFileFailed { .. } => 8,
+            ErrCode::MetricsFileFailed { .. } => 9,
+            ErrCode::DotFileFailed { .. } => 10,
+            ErrCode::SnapshotFailed { .. } => 11,
+            ErrCode::BadScenario(_) => 12,
+            ErrCode::OutputFailed(_) => 13,
+        }
+    }
+}
+
+impl Display for ErrCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrCode::Okay => write!(f, "no error"),
+            ErrCode::ShowHelpSign => write!(f, "help requested"),
+            ErrCode::ShowVersionSign => write!(f, "version requested"),
+            ErrCode::NoSwitchSpecified(arg) => {
+                write!(f, "argument \"{arg}\" doesn't follow a switch")
+            }
+            ErrCode::UnknownSwitch(arg) => write!(f, "unknown switch {arg}"),
+            ErrCode::SwitchHasNoArg(switch) => {
+                write!(f, "switch {} needs an argument", switch_name(*switch))
+            }
+            ErrCode::BadSwitchArg(switch, arg) => write!(
+                f,
+                "bad argument \"{arg}\" for switch {}",
+                switch_name(*switch)
+            ),
+            ErrCode::NtwkFileParseFailed { file, reason } => {
+                write!(f, "can't read network from {file}: {reason}")
+            }
+            ErrCode::SimFileParseFailed { file, reason } => {
+                write!(f, "can't read instructions from {file}: {reason}")
+            }
+            ErrCode::TraceFileFailed { file, source } => {
+                write!(f, "can't write trace to {file}: {source}")
+            }
+            ErrCode::MetricsFileFailed { prefix, source } => {
+                write!(f, "can't write metrics to {prefix}.*.csv: {source}")
+            }
+            ErrCode::DotFileFailed { file, source } => {
+                write!(f, "can't write drawing to {file}: {source}")
+            }
+            ErrCode::SnapshotFailed { file, reason } => write!(f, "snapshot {file}: {reason}"),
+            ErrCode::BadScenario(reason) => write!(f, "bad scenario: {reason}"),
+            ErrCode::OutputFailed(source) => write!(f, "can't write output: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for ErrCode {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ErrCode::TraceFileFailed { source, .. }
+            | ErrCode::MetricsFileFailed { source, .. }
+            | ErrCode::DotFileFailed { source, .. }
+            | ErrCode::OutputFailed(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+pub const USAGE: &str = "\
+usage: guwah [options]
+
+Runs the simulation in a .sim file over the network in a .ntwk file.
+
+options:
+  -n, --ntwk <file>       network to simulate (default config/basic.ntwk)
+  -s, --sim <file>        instructions to run (default config/basic.sim)
+                          either file may be - to read it from stdin
+  -r, --seed <n>          seed for random draws, overriding the sim file's
+  -o, --routing <how>     route by fewest hops (hops, default) or by least
+                          delay (delay)
+  -f, --format <fmt>      print reports as text (default) or json
+  -t, --trace <file>      write every event to <file> as JSON Lines
+  -m, --metrics <prefix>  write <prefix>.messages.csv and <prefix>.nodes.csv
+  -d, --dot <file>        draw the -n network to <file> in Graphviz DOT
+                          instead of running
+  -p, --dot-msg <id>      run, then draw with message <id>'s path
+                          highlighted
+  -R, --restore <file>    resume the run saved in a snapshot, with the
+                          seed and routing it was saved with, so not
+                          with -r or -o
+  -g, --generate <topo>   write a generated network to stdout instead of
+                          running; <topo> is line:N, ring:N, star:N,
+                          grid:RxC, torus:RxC, mesh:N, tree:N:FANOUT,
+                          er:N:P, ba:N:M or waxman:N:ALPHA:BETA, drawn from
+                          the -r seed (default 0)
+  -q, --queue-delay <d>   queue delay of generated nodes, in ticks or as
+                          min..max to draw it (default 1)
+  -w, --workload <load>   write random traffic over the -n network to stdout
+                          instead of running; <load> is periodic:TICKS:END
+                          or poisson:RATE:END, then any of ,skew=S (favour
+                          a few hot destinations), ,rep=TICKS and ,size=B,
+                          drawn from the -r seed (default 0)
+  -a, --analyze           print the size, paths, weak spots and busiest
+                          nodes of the -n network instead of running
+  -i, --interactive       pause before each tick for debugger commands,
+                          read from stdin, so neither file may be -
+  -v, --verbose           print every node after busy ticks; twice to also
+                          print each instruction as it fires
+  -h, --help              show this help
+  -V, --version           show the version
+
+Long options also take their argument as --option=value.
+
+exit status:
+  0   success, or help or version shown
+  2   argument without a switch     3   unknown switch
+  4   switch without its argument   7   bad switch argument
+  5   bad network file              6   bad sim file
+  8   trace file failed             9   metrics files failed
+  10  DOT file failed               11  snapshot failed
+  12  bad generator parameters or workload
+  13  output can't be written";
+
+/// How the reports printed by `rep` are laid out.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    /// One JSON object per message, per line.
+    Json,
+}
+
+/// How packets pick the neighbour to forward to.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Routing {
+    /// Fewest hops to the destination.
+    #[default]
+    Hops,
+    /// Least queueing and transmission delay to the destination.
+    Delay,
+}
+
+impl Display for Routing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Routing::Hops => write!(f, "hops"),
+            Routing::Delay => write!(f, "delay"),
+        }
+    }
+}
+
+/// A command line switch, with its long form.
+struct Switch {
+    short: char,
+    long: &'static str,
+    takes_arg: bool,
+}
+
+impl Switch {
+    const fn flag(short: char, long: &'static str) -> Switch {
+        Switch {
+            short,
+            long,
+            takes_arg: false,
+        }
+    }
+
+    const fn with_arg(short: char, long: &'static str) -> Switch {
+        Switch {
+            short,
+            long,
+            takes_arg: true,
+        }
+    }
+}
+
+static SWITCHES: [Switch; 18] = [
+    Switch::flag('h', "help"),
+    Switch::flag('V', "version"),
+    Switch::flag('v', "verbose"),
+    Switch::flag('i', "interactive"),
+    Switch::flag('a', "analyze"),
+    Switch::with_arg('n', "ntwk"),
+    Switch::with_arg('s', "sim"),
+    Switch::with_arg('r', "seed"),
+    Switch::with_arg('o', "routing"),
+    Switch::with_arg('f', "format"),
+    Switch::with_arg('t', "trace"),
+    Switch::with_arg('m', "metrics"),
+    Switch::with_arg('d', "dot"),
+    Switch::with_arg('p', "dot-msg"),
+    Switch::with_arg('R', "restore"),
+    Switch::with_arg('g', "generate"),
+    Switch::with_arg('q', "queue-delay"),
+    Switch::with_arg('w', "workload"),
+];
+
 #[derive(Debug)]
 pub struct RnsSettings {
     pub ntwk_filename: String,
     pub sim_filename: String,
+    pub seed: Option<u64>,
+    pub routing: Option<Routing>,
+    pub format: OutputFormat,
+    pub trace_filename: Option<String>,
+    pub metrics_prefix: Option<String>,
+    pub dot_filename: Option<String>,
+    pub dot_msg: Option<u32>,
+    pub verbosity: u8,
+    pub restore_filename: Option<String>,
+    pub interactive: bool,
+    /// Network to generate and print instead of running a simulation.
+    pub generate: Option<Topology>,
+    pub queue_delays: QueueDelays,
+    /// Traffic to generate and print instead of running a simulation.
+    pub workload: Option<Workload>,
+    /// Print an analysis of the network instead of running a simulation.
+    pub analyze: bool,
 }
 
 impl RnsSettings {
@@ -23,56 +304,72 @@ impl RnsSettings {
         let mut s = RnsSettings {
             ntwk_filename: String::from(DEFAULT_NTWK_FILE),
             sim_filename: String::from(DEFAULT_SIM_FILE),
+            seed: None,
+            routing: None,
+            format: OutputFormat::default(),
+            trace_filename: None,
+            metrics_prefix: None,
+            dot_filename: None,
+            dot_msg: None,
+            verbosity: 0,
+            restore_filename: None,
+            interactive: false,
+            generate: None,
+            queue_delays: QueueDelays::default(),
+            workload: None,
+            analyze: false,
         };
 
         let mut cmd_parse_status = ErrCode::Okay;
-        let mut ntwk_flag = false;
-        let mut sim_flag = false;
+        // Switch still waiting for its argument, if any
+        let mut pending: Option<char> = None;
         for arg in args.skip(1) {
-            match arg.as_str() {
-                "-h" => {
-                    if ntwk_flag || sim_flag {
-                        cmd_parse_status = ErrCode::SwitchHasNoArg;
-                    } else {
-                        cmd_parse_status = ErrCode::ShowHelpSign;
-                    }
-                    break;
-                }
-                "-n" => {
-                    if ntwk_flag || sim_flag {
-                        cmd_parse_status = ErrCode::SwitchHasNoArg;
+            let (switch, inline_arg) = match find_switch(&arg) {
+                Some(found) => found,
+                None => {
+                    // A lone "-" is an argument: it stands for stdin
+                    if arg.starts_with("--") || (arg.starts_with('-') && arg.len() == 2) {
+                        cmd_parse_status = ErrCode::UnknownSwitch(arg);
                         break;
                     }
-                    ntwk_flag = true;
-                }
-                "-s" => {
-                    if sim_flag || ntwk_flag {
-                        cmd_parse_status = ErrCode::SwitchHasNoArg;
+                    let switch = match pending.take() {
+                        Some(switch) => switch,
+                        None => {
+                            cmd_parse_status = ErrCode::NoSwitchSpecified(arg);
+                            break;
+                        }
+                    };
+                    if let Err(e) = s.set_switch(switch, &arg) {
+                        cmd_parse_status = e;
                         break;
                     }
-                    sim_flag = true;
+                    continue;
                 }
-                _ => {
-                    if arg.as_str().starts_with('-') && (arg.len() <= 2) {
-                        cmd_parse_status = ErrCode::UnknownSwitch;
-                        break;
-                    }
-                    if sim_flag && !ntwk_flag {
-                        s.sim_filename = arg.to_string();
-                        sim_flag = false;
-                    } else if ntwk_flag && !sim_flag {
-                        s.ntwk_filename = arg.to_string();
-                        ntwk_flag = false;
-                    } else {
-                        cmd_parse_status = ErrCode::NoSwitchSpecified;
-                        break;
-                    }
+            };
+
+            if let Some(pending) = pending {
+                cmd_parse_status = ErrCode::SwitchHasNoArg(pending);
+                break;
+            }
+            let result = match (switch.takes_arg, inline_arg) {
+                (true, Some(inline_arg)) => s.set_switch(switch.short, inline_arg),
+                (true, None) => {
+                    pending = Some(switch.short);
+                    Ok(())
+                }
+                (false, Some(inline_arg)) => {
+                    Err(ErrCode::BadSwitchArg(switch.short, inline_arg.to_string()))
                 }
+                (false, None) => s.set_flag(switch.short),
             };
+            if let Err(e) = result {
+                cmd_parse_status = e;
+                break;
+            }
         }
 
-        if sim_flag || ntwk_flag {
-            cmd_parse_status = ErrCode::SwitchHasNoArg;
+        if let Some(pending) = pending {
+            cmd_parse_status = ErrCode::SwitchHasNoArg(pending);
         }
 
         match cmd_parse_status {
@@ -80,4 +377,81 @@ impl RnsSettings {
             _ => Err(cmd_parse_status),
         }
     }
+
+    /// Stores the argument given to the switch `-<switch>`.
+    fn set_switch(&mut self, switch: char, arg: &str) -> Result<(), ErrCode> {
+        let bad_arg = || ErrCode::BadSwitchArg(switch, arg.to_string());
+        match switch {
+            'n' => self.ntwk_filename = arg.to_string(),
+            's' => self.sim_filename = arg.to_string(),
+            'r' => self.seed = Some(arg.parse().map_err(|_| bad_arg())?),
+            'o' => {
+                self.routing = Some(match arg {
+                    "hops" => Routing::Hops,
+                    "delay" => Routing::Delay,
+                    _ => return Err(bad_arg()),
+                })
+            }
+            'f' => {
+                self.format = match arg {
+                    "text" => OutputFormat::Text,
+                    "json" => OutputFormat::Json,
+                    _ => return Err(bad_arg()),
+                }
+            }
+            't' => self.trace_filename = Some(arg.to_string()),
+            'm' => self.metrics_prefix = Some(arg.to_string()),
+            'd' => self.dot_filename = Some(arg.to_string()),
+            'p' => self.dot_msg = Some(arg.parse().map_err(|_| bad_arg())?),
+            'R' => self.restore_filename = Some(arg.to_string()),
+            'g' => self.generate = Some(arg.parse().map_err(|_| bad_arg())?),
+            'q' => self.queue_delays = arg.parse().map_err(|_| bad_arg())?,
+            'w' => self.workload = Some(arg.parse().map_err(|_| bad_arg())?),
+            _ => return Err(ErrCode::UnknownSwitch(format!("-{switch}"))),
+        }
+        Ok(())
+    }
+
+    /// Applies a switch that takes no argument.
+    fn set_flag(&mut self, switch: char) -> Result<(), ErrCode> {
+        match switch {
+            'h' => return Err(ErrCode::ShowHelpSign),
+            'V' => return Err(ErrCode::ShowVersionSign),
+            'v' => self.verbosity = self.verbosity.saturating_add(1),
+            'i' => self.interactive = true,
+            'a' => self.analyze = true,
+            _ => return Err(ErrCode::UnknownSwitch(format!("-{switch}"))),
+        }
+        Ok(())
+    }
+}
+
+/// Looks up the switch `arg` names, either as `-x` or as `--long`, along
+/// with the argument given inline as `--long=value`.
+fn find_switch(arg: &str) -> Option<(&'static Switch, Option<&str>)> {
+    if let Some(long) = arg.strip_prefix("--") {
+        let (name, inline_arg) = match long.split_once('=') {
+            Some((name, inline_arg)) => (name, Some(inline_arg)),
+            None => (long, None),
+        };
+        let switch = SWITCHES.iter().find(|switch| switch.long == name)?;
+        return Some((switch, inline_arg));
+    }
+
+    let mut chars = arg.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some('-'), Some(short), None) => SWITCHES
+            .iter()
+            .find(|switch| switch.short == short)
+            .map(|switch| (switch, None)),
+        _ => None,
+    }
+}
+
+/// Names a switch by both its forms, e.g. `-r (--seed)`.
+fn switch_name(short: char) -> String {
+    match SWITCHES.iter().find(|switch| switch.short == short) {
+        Some(switch) => format!("-{short} (--{})", switch.long),
+        None => format!("-{short}"),
+    }
 }
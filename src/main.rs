@@ -1,34 +1,155 @@
-use guwah::{ErrCode, RnsSettings};
-use std::process;
-
-mod timeline;
+use guwah::{ErrCode, Ntwk, OutputFormat, RnsSettings, Sim, Simulator, USAGE};
+use std::{
+    collections::HashSet,
+    io::{self, BufWriter, Write},
+    process,
+};
 
 fn main() {
     // Get cmd args -> global settings
     let args = std::env::args();
     let rns_settings = RnsSettings::from_args(args).unwrap_or_else(|err| match err {
         ErrCode::ShowHelpSign => {
-            println!("Help Board :D (TODO)");
-            process::exit(ErrCode::Okay as i32);
+            println!("{USAGE}");
+            process::exit(ErrCode::Okay.exit_code());
+        }
+        ErrCode::ShowVersionSign => {
+            println!("guwah {}", env!("CARGO_PKG_VERSION"));
+            process::exit(ErrCode::Okay.exit_code());
         }
         e => {
-            let err = e as i32;
-            eprintln!("Error: {err}");
-            process::exit(err);
+            eprintln!("error: {e}");
+            eprintln!("try guwah --help");
+            process::exit(e.exit_code());
         }
     });
 
     //dbg!(&rns_settings);
 
-    timeline::start(rns_settings);
+    if let Err(e) = run(rns_settings) {
+        eprintln!("error: {e}");
+        process::exit(e.exit_code());
+    }
+}
+
+/// Filename that stands for stdin.
+const STDIN_NAME: &str = "-";
+
+fn run(settings: RnsSettings) -> Result<(), ErrCode> {
+    if let Some(topology) = settings.generate {
+        let seed = settings.seed.unwrap_or(0);
+        let ntwk = Ntwk::generate(topology, settings.queue_delays, seed)?;
+        return print_generated(format!("{topology}, seed {seed}"), |out| {
+            ntwk.write_ntwk(out)
+        });
+    }
+    if let Some(workload) = &settings.workload {
+        let seed = settings.seed.unwrap_or(0);
+        let sim = Sim::generate(workload, &read_ntwk(&settings.ntwk_filename)?, seed)?;
+        return print_generated(format!("{workload}, seed {seed}"), |out| sim.write_sim(out));
+    }
+
+    if settings.analyze {
+        let analysis = read_ntwk(&settings.ntwk_filename)?.analyze();
+        match settings.format {
+            OutputFormat::Text => {
+                writeln!(io::stdout().lock(), "{analysis}").map_err(ErrCode::OutputFailed)?
+            }
+            OutputFormat::Json => analysis
+                .write_json(&mut io::stdout().lock())
+                .map_err(ErrCode::OutputFailed)?,
+        }
+        return Ok(());
+    }
+
+    // Without a message to highlight, the drawing doesn't need the run
+    if let (Some(filename), None) = (&settings.dot_filename, settings.dot_msg) {
+        return read_ntwk(&settings.ntwk_filename)?.write_dot_file(filename, &HashSet::new());
+    }
+
+    // The debugger reads its commands from stdin, so the files can't come from there
+    if settings.interactive {
+        if settings.sim_filename == STDIN_NAME {
+            return Err(ErrCode::BadSwitchArg('s', STDIN_NAME.to_string()));
+        }
+        if settings.ntwk_filename == STDIN_NAME && settings.restore_filename.is_none() {
+            return Err(ErrCode::BadSwitchArg('n', STDIN_NAME.to_string()));
+        }
+    }
+
+    // A resumed run carries on with the seed and routes it was saved with
+    if settings.restore_filename.is_some() {
+        if let Some(seed) = settings.seed {
+            return Err(ErrCode::BadSwitchArg('r', seed.to_string()));
+        }
+        if let Some(routing) = settings.routing {
+            return Err(ErrCode::BadSwitchArg('o', routing.to_string()));
+        }
+    }
+
+    // A run resumed from a snapshot takes its network from the snapshot
+    let mut simulator = match &settings.restore_filename {
+        Some(filename) => Simulator::resume(filename, read_sim(&settings.sim_filename)?)?,
+        None => {
+            if settings.ntwk_filename == STDIN_NAME && settings.sim_filename == STDIN_NAME {
+                return Err(ErrCode::BadSwitchArg('s', STDIN_NAME.to_string()));
+            }
+            let ntwk = read_ntwk(&settings.ntwk_filename)?;
+            let mut simulator = Simulator::new(ntwk, read_sim(&settings.sim_filename)?);
+            simulator.routing(settings.routing.unwrap_or_default());
+            simulator
+        }
+    };
+
+    simulator
+        .format(settings.format)
+        .verbosity(settings.verbosity)
+        .interactive(settings.interactive);
+    if let Some(seed) = settings.seed {
+        simulator.seed(seed);
+    }
+    if let Some(filename) = &settings.trace_filename {
+        simulator.trace_file(filename);
+    }
+    if let Some(prefix) = &settings.metrics_prefix {
+        simulator.metrics_prefix(prefix);
+    }
+    if let Some(filename) = &settings.dot_filename {
+        simulator.dot_file(filename);
+    }
+    if let Some(id) = settings.dot_msg {
+        simulator.dot_msg(id);
+    }
+
+    for problem in simulator.check() {
+        eprintln!("warning: {problem}");
+    }
+    simulator.run().map(|_| ())
 }
 
-impl timeline::Settings for RnsSettings {
-    fn ntwk_file(&self) -> &String {
-        &self.ntwk_filename
+fn read_ntwk(filename: &str) -> Result<Ntwk, ErrCode> {
+    match filename {
+        STDIN_NAME => Ntwk::from_reader(io::stdin().lock()),
+        _ => Ntwk::from_file(filename),
     }
+}
 
-    fn sim_file(&self) -> &String {
-        &self.sim_filename
+fn read_sim(filename: &str) -> Result<Sim, ErrCode> {
+    match filename {
+        STDIN_NAME => Sim::from_reader(io::stdin().lock()),
+        _ => Sim::from_file(filename),
     }
 }
+
+/// Writes a generated network or sim to stdout, after a comment saying
+/// how it was generated.
+fn print_generated<F>(how: String, write: F) -> Result<(), ErrCode>
+where
+    F: FnOnce(&mut BufWriter<io::StdoutLock<'static>>) -> io::Result<()>,
+{
+    let mut out = BufWriter::new(io::stdout().lock());
+    writeln!(out, "# {how}")
+        .and_then(|_| write(&mut out))
+        .and_then(|_| out.flush())
+        .map_err(ErrCode::OutputFailed)
+}
@@ -1,76 +1,413 @@
 use crate::{
     timeline::{
-        msg::packet::Packet,
-        ntwk::Ntwk,
-        sim::{Sim, SimIntoTimeline},
+        debugger::{Debugger, Resume},
+        engine::Engine,
+        sim::SimIntoTimeline,
+        snapshot::{Snap, SnapReader},
+        trace::Trace,
+        utils::rng::Rng,
     },
-    ErrCode,
+    ErrCode, OutputFormat, Routing,
 };
 
 use core::{iter::Zip, ops::RangeFrom};
+use std::{collections::HashSet, io};
 
-use self::msg::avl_tree::AvlTree;
+pub use self::{
+    engine::NodeStats,
+    msg::{
+        packet::Hop,
+        report::{AckOutcome, AckReport, Delivery, DropReason, MsgKind, MsgReport, MsgStatus},
+    },
+    ntwk::{
+        analysis::Analysis,
+        builder::NtwkBuilder,
+        generate::{QueueDelays, Topology},
+        Ntwk,
+    },
+    sim::{
+        builder::SimBuilder,
+        check::SimProblem,
+        instr::Instr,
+        workload::{ArrivalProcess, Workload},
+        Sim,
+    },
+};
 
+mod debugger;
+mod engine;
+mod metrics;
 mod msg;
 mod ntwk;
 mod sim;
+mod snapshot;
+mod trace;
 mod utils;
 
-pub fn start<T>(settings: T) -> i32
-where
-    T: Settings,
-{
-    // Open ntwk file and parse according to settings
-    let ntwk = match Ntwk::from_file(settings.ntwk_file()) {
-        Ok(n) => n,
-        Err(e) => {
-            eprintln!("ntwk parse error");
-            return e as i32;
+/// Runs the instructions of a [`Sim`] over a [`Ntwk`].
+///
+/// Reports asked for by `rep` instructions are printed as the run goes,
+/// and everything the run did is handed back as a [`RunResult`].
+///
+/// ```no_run
+/// use guwah::{Ntwk, Sim, Simulator};
+///
+/// let ntwk = Ntwk::from_file("config/basic.ntwk")?;
+/// let sim = Sim::from_file("config/basic.sim")?;
+/// let result = Simulator::new(ntwk, sim).seed(7).run()?;
+/// println!("{} messages", result.reports().len());
+/// # Ok::<(), guwah::ErrCode>(())
+/// ```
+pub struct Simulator {
+    ntwk: Ntwk,
+    sim: Sim,
+    /// Snapshot the run resumes from, with the tick it was taken at and
+    /// a reader positioned at the engine state.
+    restore: Option<(String, u32, SnapReader)>,
+    seed: Option<u64>,
+    format: OutputFormat,
+    verbosity: u8,
+    trace_file: Option<String>,
+    metrics_prefix: Option<String>,
+    dot_file: Option<String>,
+    dot_msg: Option<u32>,
+    interactive: bool,
+}
+
+impl Simulator {
+    pub fn new(ntwk: Ntwk, sim: Sim) -> Simulator {
+        Simulator {
+            ntwk,
+            sim,
+            restore: None,
+            seed: None,
+            format: OutputFormat::default(),
+            verbosity: 0,
+            trace_file: None,
+            metrics_prefix: None,
+            dot_file: None,
+            dot_msg: None,
+            interactive: false,
         }
-    };
+    }
+
+    /// Resumes the run saved in the snapshot `filename`, which brings its
+    /// own network, routes and seed. Instructions of `sim` up to the tick the
+    /// snapshot was taken at are skipped.
+    pub fn resume(filename: &str, sim: Sim) -> Result<Simulator, ErrCode> {
+        let failed = |e: snapshot::SnapErrCode| ErrCode::SnapshotFailed {
+            file: filename.to_string(),
+            reason: e.to_string(),
+        };
+        let (tick, mut inp) = snapshot::open(filename).map_err(failed)?;
+        let ntwk = Ntwk::load(&mut inp).map_err(failed)?;
+        Ok(Simulator {
+            restore: Some((filename.to_string(), tick, inp)),
+            ..Simulator::new(ntwk, sim)
+        })
+    }
+
+    /// Seed for every random draw in the run, overriding the sim file's.
+    /// A resumed run keeps drawing from where the snapshot left off, so this
+    /// has no effect on it.
+    pub fn seed(&mut self, seed: u64) -> &mut Simulator {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Rebuilds the network's routes with `routing`. Meant for a fresh run:
+    /// a resumed one should keep the routes its snapshot was taken with.
+    pub fn routing(&mut self, routing: Routing) -> &mut Simulator {
+        self.ntwk.set_routing(routing);
+        self
+    }
+
+    /// How to lay out the reports `rep` prints.
+    pub fn format(&mut self, format: OutputFormat) -> &mut Simulator {
+        self.format = format;
+        self
+    }
+
+    /// How much to print as the run goes: at 1 a table of every node after
+    /// each busy tick, at 2 also each instruction as it fires.
+    pub fn verbosity(&mut self, verbosity: u8) -> &mut Simulator {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Writes a JSON Lines trace of every event to `filename`.
+    pub fn trace_file(&mut self, filename: &str) -> &mut Simulator {
+        self.trace_file = Some(filename.to_string());
+        self
+    }
+
+    /// Writes metrics as CSV files starting with `prefix` at the end.
+    pub fn metrics_prefix(&mut self, prefix: &str) -> &mut Simulator {
+        self.metrics_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Writes a Graphviz drawing of the network to `filename`.
+    pub fn dot_file(&mut self, filename: &str) -> &mut Simulator {
+        self.dot_file = Some(filename.to_string());
+        self
+    }
+
+    /// Highlights the path of message `id` in the drawing, which is then
+    /// made after the run instead of before it.
+    pub fn dot_msg(&mut self, id: u32) -> &mut Simulator {
+        self.dot_msg = Some(id);
+        self
+    }
+
+    /// Pauses before each tick and takes debugger commands from stdin.
+    pub fn interactive(&mut self, interactive: bool) -> &mut Simulator {
+        self.interactive = interactive;
+        self
+    }
 
-    //dbg!(&ntwk);
+    /// Looks through the sim for instructions that don't fit the network,
+    /// like messages to nodes it doesn't have. The run goes ahead regardless,
+    /// so these are only worth warning about.
+    pub fn check(&self) -> Vec<SimProblem> {
+        self.sim.check(&self.ntwk)
+    }
 
-    // Open sim file and parse according to settings
-    let sims = match Sim::from_file(settings.sim_file()) {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("sim parse error");
-            return e as i32;
+    /// Runs the simulation to its end.
+    pub fn run(&self) -> Result<RunResult, ErrCode> {
+        let ntwk = &self.ntwk;
+
+        // Without a message to highlight, the graph doesn't need the run
+        if let (Some(filename), None) = (&self.dot_file, self.dot_msg) {
+            ntwk.write_dot_file(filename, &HashSet::new())?;
+        }
+
+        // Instructions up to the snapshot's tick already ran before it was taken
+        let (mut engine, resume_after) = match &self.restore {
+            Some((filename, tick, inp)) => {
+                let engine =
+                    Engine::load(ntwk, &mut inp.clone()).map_err(|e| ErrCode::SnapshotFailed {
+                        file: filename.clone(),
+                        reason: e.to_string(),
+                    })?;
+                (engine, Some(*tick))
+            }
+            None => {
+                // A seed given on the command line wins over one set in the sim file
+                let seed = self
+                    .seed
+                    .or(self.sim.seed())
+                    .unwrap_or_else(Rng::fresh_seed);
+                (Engine::new(ntwk, seed), None)
+            }
+        };
+        if let Some(filename) = &self.trace_file {
+            let trace = Trace::to_file(filename).map_err(|source| ErrCode::TraceFileFailed {
+                file: filename.clone(),
+                source,
+            })?;
+            engine.set_trace(trace);
         }
-    };
-
-    let tree: AvlTree<Packet> = AvlTree::new();
-    timeline(0, &sims).for_each(|(timestep, maybe_instr_set)| {
-        if let Some(instr_set) = maybe_instr_set {
-            for instr in instr_set.iter() {
-                match instr {
-                    sim::instr::Instr::Msg(m) => {
-                        let pack = Packet::Message(m.into());
+        let mut debugger = self
+            .interactive
+            .then(|| Debugger::new(io::stdin().lock(), self.format));
+        let mut stdout = io::stdout();
+        let mut end_tick = 0;
+        'ticks: for (timestep, maybe_instr_set) in timeline(0, &self.sim) {
+            if resume_after.is_some_and(|tick| timestep <= tick) {
+                continue;
+            }
+            end_tick = timestep;
+            if let Some(debugger) = debugger.as_mut() {
+                if let Resume::Quit = debugger.before_tick(timestep, maybe_instr_set, &engine) {
+                    break 'ticks;
+                }
+            }
+            let handled = engine.events_handled();
+            let mut snapshot_file: Option<&str> = None;
+            // Let packets already in the network move before new instructions fire
+            engine.run_until(timestep);
+
+            if let Some(instr_set) = maybe_instr_set {
+                for instr in instr_set.iter() {
+                    engine.trace_instr(timestep, instr);
+                    if self.verbosity >= 2 {
+                        match instr.msg_id() {
+                            Some(id) => println!("t={timestep}: {} {id}", instr.name()),
+                            None => println!("t={timestep}: {}", instr.name()),
+                        }
+                    }
+                    match instr {
+                        Instr::Msg(m) => engine.inject(m),
+                        Instr::Bcast(b) => engine.inject_bcast(b),
+                        Instr::Mcast(m) => engine.inject_mcast(m),
+                        Instr::Flow(f) => engine.add_flow(f),
+                        Instr::Rep(r) => match r.msg_id() {
+                            Some(id) => print_report(&engine, timestep, id, self.format),
+                            None => print_reports(&engine, timestep, self.format),
+                        },
+                        Instr::Snapshot(s) => snapshot_file = Some(s.filename()),
+                        Instr::EndSim(_) => break 'ticks,
                     }
-                    sim::instr::Instr::Rep(r) => match r.msg_id() {
-                        Some(_) => todo!(),
-                        None => tree
-                            .iter()
-                            .for_each(|msg_wrap| println!("{}", &msg_wrap.get())),
-                    },
-                    sim::instr::Instr::EndSim(_) => todo!(),
                 }
+            }
 
-                // Add instructions to avl tree for fast search,
-                // and to vec stack for in-order reports
+            if let Some(filename) = snapshot_file {
+                snapshot::save(filename, timestep, ntwk, &engine).map_err(|e| {
+                    ErrCode::SnapshotFailed {
+                        file: filename.to_string(),
+                        reason: e.to_string(),
+                    }
+                })?;
+            }
+
+            if self.verbosity >= 1 && engine.events_handled() != handled {
+                if let Err(e) = engine.write_tick_table(&mut stdout, timestep) {
+                    eprintln!("{e}");
+                }
             }
         }
-    });
 
-    ErrCode::Okay as i32
+        if let Some(prefix) = &self.metrics_prefix {
+            metrics::write_csv(prefix, &engine).map_err(|source| ErrCode::MetricsFileFailed {
+                prefix: prefix.clone(),
+                source,
+            })?;
+        }
+
+        if let (Some(filename), Some(id)) = (&self.dot_file, self.dot_msg) {
+            let highlight = path_links(&engine, id).unwrap_or_else(|| {
+                eprintln!("msg {id}: no such message, no path highlighted");
+                HashSet::new()
+            });
+            ntwk.write_dot_file(filename, &highlight)?;
+        }
+
+        Ok(RunResult {
+            seed: engine.seed(),
+            end_tick,
+            reports: engine
+                .reports()
+                .iter()
+                .map(|msg_wrap| msg_wrap.get().clone())
+                .collect(),
+            nodes: engine.node_stats().collect(),
+        })
+    }
 }
 
-fn timeline(start_time: u32, sims: &Sim) -> Zip<RangeFrom<u32>, SimIntoTimeline<'_>> {
-    (start_time..).zip(sims.into_timeline())
+/// What a finished run left behind.
+#[derive(Debug, Clone)]
+pub struct RunResult {
+    seed: u64,
+    end_tick: u32,
+    /// In order of message ID, then of nth.
+    reports: Vec<MsgReport>,
+    /// In order of node ID.
+    nodes: Vec<(u32, NodeStats)>,
+}
+
+impl RunResult {
+    /// Seed the run's random draws came from, to repeat it with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Last tick the run got to.
+    pub fn end_tick(&self) -> u32 {
+        self.end_tick
+    }
+
+    /// Report on every message, in order of ID. Messages sharing an ID
+    /// follow each other in the order they were sent.
+    pub fn reports(&self) -> &[MsgReport] {
+        &self.reports
+    }
+
+    /// Report on message `id`, or on the first message sent with it if
+    /// the sim reused the ID.
+    pub fn report(&self, id: u32) -> Option<&MsgReport> {
+        self.reports_for(id).first()
+    }
+
+    /// Reports on every message sent with ID `id`, in the order they were
+    /// sent; more than one if the sim reused the ID.
+    pub fn reports_for(&self, id: u32) -> &[MsgReport] {
+        let start = self.reports.partition_point(|report| report.id() < id);
+        let end = self.reports.partition_point(|report| report.id() <= id);
+        &self.reports[start..end]
+    }
+
+    /// Totals for every node, in order of node ID.
+    pub fn node_stats(&self) -> &[(u32, NodeStats)] {
+        &self.nodes
+    }
+}
+
+/// Prints the report of message `id`, or of every message sent with that
+/// ID if the sim used it more than once.
+fn print_report(engine: &Engine, tick: u32, id: u32, format: OutputFormat) {
+    let mut found = false;
+    for rep in engine.reports().iter().filter(|rep| rep.get().id() == id) {
+        found = true;
+        match format {
+            OutputFormat::Text => println!("{}", &rep.get()),
+            OutputFormat::Json => {
+                if let Err(e) = rep.get().write_json(&mut io::stdout(), tick) {
+                    eprintln!("{e}");
+                }
+            }
+        }
+    }
+    match (found, format) {
+        (true, _) => (),
+        (false, OutputFormat::Text) => println!("msg {id}: no such message"),
+        (false, OutputFormat::Json) => {
+            println!("{{\"tick\":{tick},\"id\":{id},\"error\":\"no such message\"}}")
+        }
+    }
 }
 
-pub trait Settings {
-    fn ntwk_file(&self) -> &String;
-    fn sim_file(&self) -> &String;
+fn print_reports(engine: &Engine, tick: u32, format: OutputFormat) {
+    match format {
+        OutputFormat::Text => {
+            println!("report at t={tick} (seed {})", engine.seed());
+            engine
+                .reports()
+                .iter()
+                .for_each(|msg_wrap| println!("{}", &msg_wrap.get()))
+        }
+        OutputFormat::Json => {
+            let mut stdout = io::stdout().lock();
+            for msg_wrap in engine.reports().iter() {
+                if let Err(e) = msg_wrap.get().write_json(&mut stdout, tick) {
+                    eprintln!("{e}");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Links the message `id` travelled across, to every destination it has,
+/// taking in every message sent with that ID.
+fn path_links(engine: &Engine, id: u32) -> Option<HashSet<(u32, u32)>> {
+    let mut found = false;
+    let mut links = HashSet::new();
+    for report in engine.reports().iter().filter(|rep| rep.get().id() == id) {
+        found = true;
+        for (_, delivery) in report.get().deliveries() {
+            links.extend(
+                delivery
+                    .path()
+                    .windows(2)
+                    .map(|pair| (pair[0].node(), pair[1].node())),
+            );
+        }
+    }
+    found.then_some(links)
+}
+
+fn timeline(start_time: u32, sims: &Sim) -> Zip<RangeFrom<u32>, SimIntoTimeline<'_>> {
+    (start_time..).zip(sims.into_timeline())
 }
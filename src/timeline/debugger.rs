@@ -0,0 +1,173 @@
+use std::{
+    fmt::Display,
+    io::{self, BufRead, Write},
+};
+
+use crate::{
+    timeline::{engine::Engine, sim::InstrSet},
+    OutputFormat,
+};
+
+const HELP: &str = "\
+commands:
+  step [n]            run n ticks (default 1), then pause
+  run-until <tick>    run until just before <tick>
+  continue            run until a breakpoint or the end
+  break msg <id>      pause before ticks where message <id> moves
+  break node <id>     pause before ticks where something happens at node <id>
+  show node <id>      what node <id> holds
+  show packet <id>    where the packets of message <id> are
+  rep                 report on every message so far
+  quit                stop the run";
+
+/// What the run does once the debugger lets it carry on.
+pub enum Resume {
+    Run,
+    Quit,
+}
+
+#[derive(PartialEq)]
+enum Breakpoint {
+    Msg(u32),
+    Node(u32),
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Msg(id) => write!(f, "msg {id}"),
+            Breakpoint::Node(id) => write!(f, "node {id}"),
+        }
+    }
+}
+
+/// Pauses a run before its ticks and takes commands from `input`, one per
+/// line.
+///
+/// When `input` runs out, the rest of the run plays out without pausing,
+/// so a script of commands doesn't have to end with `continue`.
+pub struct Debugger<R: BufRead> {
+    input: R,
+    /// Tick to pause before next, or `None` to run until a breakpoint.
+    pause_at: Option<u32>,
+    breakpoints: Vec<Breakpoint>,
+    format: OutputFormat,
+}
+
+impl<R: BufRead> Debugger<R> {
+    pub fn new(input: R, format: OutputFormat) -> Debugger<R> {
+        Debugger {
+            input,
+            pause_at: Some(0),
+            breakpoints: Vec::new(),
+            format,
+        }
+    }
+
+    /// Called before the engine runs `tick`; pauses for commands if a
+    /// step ran out or a breakpoint was hit.
+    pub fn before_tick(&mut self, tick: u32, instrs: Option<&InstrSet>, engine: &Engine) -> Resume {
+        let hit = self
+            .breakpoints
+            .iter()
+            .find(|bp| Self::is_hit(bp, tick, instrs, engine));
+        if let Some(bp) = hit {
+            println!("breakpoint: {bp} at t={tick}");
+        } else if self.pause_at.is_none_or(|at| tick < at) {
+            return Resume::Run;
+        }
+
+        let mut stdout = io::stdout();
+        loop {
+            print!("t={tick}> ");
+            let _ = stdout.flush();
+            let mut line = String::new();
+            match self.input.read_line(&mut line) {
+                Ok(0) | Err(_) => {
+                    println!();
+                    self.pause_at = None;
+                    self.breakpoints.clear();
+                    return Resume::Run;
+                }
+                Ok(_) => (),
+            }
+            if let Some(resume) = self.command(line.trim(), tick, engine) {
+                return resume;
+            }
+        }
+    }
+
+    fn is_hit(bp: &Breakpoint, tick: u32, instrs: Option<&InstrSet>, engine: &Engine) -> bool {
+        match *bp {
+            Breakpoint::Msg(id) => {
+                engine.msg_due(tick, id)
+                    || instrs.is_some_and(|set| set.iter().any(|instr| instr.msg_id() == Some(id)))
+            }
+            Breakpoint::Node(node) => engine.node_due(tick, node),
+        }
+    }
+
+    /// Carries out one command, returning how to go on if it resumes the run.
+    fn command(&mut self, line: &str, tick: u32, engine: &Engine) -> Option<Resume> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => (),
+            ["step"] => {
+                self.pause_at = Some(tick + 1);
+                return Some(Resume::Run);
+            }
+            ["step", n] => match n.parse::<u32>() {
+                Ok(n) if n > 0 => {
+                    self.pause_at = Some(tick.saturating_add(n));
+                    return Some(Resume::Run);
+                }
+                _ => println!("step: bad tick count {n}"),
+            },
+            ["run-until", at] => match at.parse::<u32>() {
+                Ok(at) if at > tick => {
+                    self.pause_at = Some(at);
+                    return Some(Resume::Run);
+                }
+                Ok(at) => println!("run-until: t={at} is not after t={tick}"),
+                Err(_) => println!("run-until: bad tick {at}"),
+            },
+            ["continue"] => {
+                self.pause_at = None;
+                return Some(Resume::Run);
+            }
+            ["break", what, id] => match (*what, id.parse::<u32>()) {
+                ("msg", Ok(id)) => self.add_breakpoint(Breakpoint::Msg(id)),
+                ("node", Ok(id)) => self.add_breakpoint(Breakpoint::Node(id)),
+                _ => println!("break: expected msg <id> or node <id>"),
+            },
+            ["show", "node", id] => match id.parse::<u32>() {
+                Ok(id) => match engine.write_node(&mut io::stdout(), id) {
+                    Ok(true) => (),
+                    Ok(false) => println!("node {id}: no such node"),
+                    Err(e) => eprintln!("{e}"),
+                },
+                Err(_) => println!("show: bad node {id}"),
+            },
+            ["show", "packet", id] => match id.parse::<u32>() {
+                Ok(id) => {
+                    if let Err(e) = engine.write_packet(&mut io::stdout(), id) {
+                        eprintln!("{e}");
+                    }
+                }
+                Err(_) => println!("show: bad message {id}"),
+            },
+            ["rep"] => super::print_reports(engine, tick, self.format),
+            ["quit"] => return Some(Resume::Quit),
+            ["help"] => println!("{HELP}"),
+            _ => println!("unknown command: {line} (try help)"),
+        }
+        None
+    }
+
+    fn add_breakpoint(&mut self, bp: Breakpoint) {
+        println!("breakpoint on {bp}");
+        if !self.breakpoints.contains(&bp) {
+            self.breakpoints.push(bp);
+        }
+    }
+}
@@ -0,0 +1,761 @@
+pub use self::node_state::NodeStats;
+use self::{
+    event::{Event, EventKind},
+    flow::FlowGen,
+    node_state::NodeState,
+    reassembly::Reassembly,
+    reliable::ReliableSend,
+};
+use crate::timeline::{
+    msg::{
+        avl_tree::AvlTree,
+        packet::{AckPacket, MessagePacket, Packet},
+        report::{AckOutcome, Delivery, DropReason, MsgReport, MsgStatus},
+    },
+    ntwk::{
+        link::{Jitter, NtwkLink},
+        Ntwk,
+    },
+    sim::instr::{BcastInstr, FlowInstr, Instr, McastInstr, MsgInstr},
+    trace::{PacketTag, Trace, TraceEvent},
+    utils::rng::{Rng, Stream},
+};
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BTreeMap, BinaryHeap, HashMap},
+};
+
+mod event;
+mod flow;
+mod node_state;
+mod reassembly;
+mod reliable;
+mod snapshot;
+mod view;
+
+/// Ticks an end node waits for the rest of a fragmented message, counted
+/// from the arrival of its first fragment, before giving up on it.
+const REASSEMBLY_TIMEOUT: u32 = 50;
+
+/// Moves packets through a network one tick at a time.
+///
+/// Every node services one packet at a time: a packet waits in the node's
+/// queue, spends the node's `queue_delay` ticks in service, and is then sent
+/// across the link towards its next hop, which takes the link's transmission
+/// time. Packets are delivered as soon as they arrive at their end node.
+///
+/// Packets larger than a link's MTU are split into fragments before they're
+/// sent, and the fragments travel on independently until the end node puts
+/// them back together. Broadcasts are put back together at every node they
+/// reach, before being delivered and flooded on.
+///
+/// Messages sent in reliable mode are acknowledged by their end node, and
+/// the start node resends them until an ACK comes back or it runs out of
+/// retries.
+///
+/// Multicasts are sent as one copy per destination. Broadcasts are flooded:
+/// every node delivers the first copy it sees, forwards it out of every link
+/// but the one it came in on, and discards any later copies.
+///
+/// Traffic generators live in the scheduler too, each one sending its next
+/// message when its event comes due.
+///
+/// Lossy links lose each packet independently, and links with jitter delay
+/// each packet's arrival by a random number of ticks, so packets can
+/// overtake each other. Every link draws from its own random stream.
+///
+/// A sim may send more than one message with the same ID. Each gets its
+/// own report, told apart by counting the messages sent with that ID.
+pub struct Engine<'a> {
+    ntwk: &'a Ntwk,
+    seed: u64,
+    nodes: BTreeMap<u32, NodeState>,
+    /// Keyed by end node, message ID and nth.
+    reassemblies: HashMap<(u32, u32, u32), Reassembly>,
+    /// Keyed by message ID and nth.
+    reliable: HashMap<(u32, u32), ReliableSend>,
+    /// Number of messages sent with each ID so far.
+    ids_used: HashMap<u32, u32>,
+    flows: Vec<FlowGen>,
+    link_rngs: HashMap<(u32, u32), Rng>,
+    events: BinaryHeap<Reverse<Event>>,
+    next_seq: u64,
+    handled: u64,
+    reports: AvlTree<MsgReport>,
+    trace: Trace,
+}
+
+impl<'a> Engine<'a> {
+    /// Creates an engine whose random streams are all derived from `seed`.
+    pub fn new(ntwk: &'a Ntwk, seed: u64) -> Engine<'a> {
+        Engine {
+            ntwk,
+            seed,
+            nodes: ntwk
+                .node_ids()
+                .map(|id| (id, NodeState::default()))
+                .collect(),
+            reassemblies: HashMap::new(),
+            reliable: HashMap::new(),
+            ids_used: HashMap::new(),
+            flows: Vec::new(),
+            link_rngs: HashMap::new(),
+            events: BinaryHeap::new(),
+            next_seq: 0,
+            handled: 0,
+            reports: AvlTree::new(),
+            trace: Trace::none(),
+        }
+    }
+
+    /// Sends a record of every event from now on to `trace`.
+    pub fn set_trace(&mut self, trace: Trace) {
+        self.trace = trace;
+    }
+
+    /// Records an instruction from the sim file firing at `tick`.
+    pub fn trace_instr(&mut self, tick: u32, instr: &Instr) {
+        self.trace.record(
+            tick,
+            TraceEvent::Instr {
+                name: instr.name(),
+                id: instr.msg_id(),
+            },
+        );
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn reports(&self) -> &AvlTree<MsgReport> {
+        &self.reports
+    }
+
+    /// Statistics of every node, in order of node ID.
+    pub fn node_stats(&self) -> impl Iterator<Item = (u32, NodeStats)> + '_ {
+        self.nodes.iter().map(|(&id, state)| (id, state.stats()))
+    }
+
+    /// Hands a new message to its start node at the instruction's timestamp.
+    pub fn inject(&mut self, msg: &MsgInstr) {
+        self.send(msg);
+        self.run_until(msg.timestamp());
+    }
+
+    /// Starts a traffic generator, which keeps sending messages on its own
+    /// until it has sent all of them.
+    pub fn add_flow(&mut self, flow: &FlowInstr) {
+        let mut gen = FlowGen::new(flow.clone(), self.seed);
+        if let Some(due) = gen.next_due() {
+            let flow = self.flows.len();
+            self.schedule(due, EventKind::Generate { flow });
+        }
+        self.flows.push(gen);
+        self.run_until(flow.timestamp());
+    }
+
+    fn send(&mut self, msg: &MsgInstr) {
+        let mut packet: MessagePacket = msg.into();
+        packet.set_nth(self.next_nth(packet.id()));
+        self.reports.insert(MsgReport::new(&packet));
+        let node = packet.start_node();
+
+        if let Some(reliability) = msg.reliability() {
+            let now = msg.timestamp();
+            let send = ReliableSend::new(packet.resend(now, 1), reliability, now);
+            self.schedule(
+                now + send.rto(),
+                EventKind::RetransmitTimeout {
+                    msg_id: packet.id(),
+                    nth: packet.nth(),
+                    attempt: 1,
+                },
+            );
+            self.reliable.insert((packet.id(), packet.nth()), send);
+        }
+
+        self.schedule(
+            msg.timestamp(),
+            EventKind::Arrive {
+                node,
+                packet: Packet::Message(packet),
+            },
+        );
+    }
+
+    /// Counts one more message sent with ID `msg_id`, returning its nth.
+    fn next_nth(&mut self, msg_id: u32) -> u32 {
+        let used = self.ids_used.entry(msg_id).or_insert(0);
+        *used += 1;
+        *used
+    }
+
+    /// Hands a copy of a multicast to its start node for each destination.
+    pub fn inject_mcast(&mut self, mcast: &McastInstr) {
+        let nth = self.next_nth(mcast.msg_id());
+        let mut packets = MessagePacket::multicast(mcast);
+        packets.iter_mut().for_each(|packet| packet.set_nth(nth));
+        if let Some(first) = packets.first() {
+            self.reports
+                .insert(MsgReport::multicast(first, mcast.end_nodes()));
+        }
+
+        for packet in packets {
+            self.schedule(
+                mcast.timestamp(),
+                EventKind::Arrive {
+                    node: packet.start_node(),
+                    packet: Packet::Message(packet),
+                },
+            );
+        }
+        self.run_until(mcast.timestamp());
+    }
+
+    /// Hands a broadcast to its start node to be flooded from there.
+    pub fn inject_bcast(&mut self, bcast: &BcastInstr) {
+        let mut packet: MessagePacket = bcast.into();
+        packet.set_nth(self.next_nth(packet.id()));
+        self.reports
+            .insert(MsgReport::broadcast(&packet, self.ntwk.node_ids()));
+
+        self.schedule(
+            bcast.timestamp(),
+            EventKind::Arrive {
+                node: packet.start_node(),
+                packet: Packet::Message(packet),
+            },
+        );
+        self.run_until(bcast.timestamp());
+    }
+
+    /// Handles every scheduled event due at or before `tick`.
+    pub fn run_until(&mut self, tick: u32) {
+        while let Some(Reverse(event)) = self.events.peek() {
+            if event.tick() > tick {
+                break;
+            }
+            // Should be okay to unwrap, we just peeked at it
+            let Reverse(event) = self.events.pop().unwrap();
+            self.handled += 1;
+            let now = event.tick();
+            match event.into_kind() {
+                EventKind::Arrive { node, packet } => self.arrive(now, node, packet),
+                EventKind::ServiceDone { node } => self.service_done(now, node),
+                EventKind::ReassemblyTimeout { node, msg_id, nth } => {
+                    self.reassembly_timeout(now, node, msg_id, nth)
+                }
+                EventKind::RetransmitTimeout {
+                    msg_id,
+                    nth,
+                    attempt,
+                } => self.retransmit_timeout(now, msg_id, nth, attempt),
+                EventKind::Generate { flow } => self.generate(now, flow),
+            }
+        }
+    }
+
+    fn schedule(&mut self, tick: u32, kind: EventKind) {
+        self.events
+            .push(Reverse(Event::new(tick, self.next_seq, kind)));
+        self.next_seq += 1;
+    }
+
+    fn arrive(&mut self, now: u32, node: u32, mut packet: Packet) {
+        packet.set_current_node(node, now);
+        self.trace.record(
+            now,
+            TraceEvent::Receive {
+                node,
+                packet: (&packet).into(),
+            },
+        );
+
+        let packet = match packet {
+            Packet::Message(m) if m.is_broadcast() => match self.receive_broadcast(now, node, m) {
+                Some(m) => Packet::Message(m),
+                None => return,
+            },
+            packet => packet,
+        };
+
+        if node == packet.end_node() {
+            self.deliver(now, node, packet);
+            return;
+        }
+
+        let capacity = match self.ntwk.node(node) {
+            Some(n) => n.queue_capacity(),
+            None => {
+                self.drop_packet(now, node, &packet, DropReason::UnknownNode);
+                return;
+            }
+        };
+        // Every node in the network has a state, so indexing is fine
+        let queued_bytes = self.nodes[&node].queued_bytes();
+        if let Some(capacity) = capacity {
+            if queued_bytes + packet.size() > capacity {
+                self.drop_packet(now, node, &packet, DropReason::QueueFull);
+                return;
+            }
+        }
+
+        self.set_status(&packet, node, MsgStatus::Queued { node });
+        self.trace.record(
+            now,
+            TraceEvent::Enqueue {
+                node,
+                packet: (&packet).into(),
+            },
+        );
+        // Should be okay to unwrap, checked above
+        self.nodes.get_mut(&node).unwrap().push(now, packet);
+        self.try_serve(now, node);
+    }
+
+    fn service_done(&mut self, now: u32, node: u32) {
+        // Should be okay to unwrap, service only starts on known nodes
+        let mut packet = match self.nodes.get_mut(&node).unwrap().finish_service() {
+            Some(packet) => packet,
+            None => return,
+        };
+
+        if let Packet::Message(m) = &mut packet {
+            if m.is_broadcast() {
+                m.add_hop();
+                self.flood(now, node, m);
+                self.try_serve(now, node);
+                return;
+            }
+        }
+
+        match self.route(node, packet.end_node()) {
+            Some(link) => {
+                if let Packet::Message(m) = &mut packet {
+                    m.add_hop();
+                }
+                self.transmit(now, node, link, packet);
+            }
+            None => self.drop_packet(now, node, &packet, DropReason::NoRoute),
+        }
+
+        self.try_serve(now, node);
+    }
+
+    /// Looks up the link `node` should use to forward towards `dest`.
+    fn route(&self, node: u32, dest: u32) -> Option<NtwkLink> {
+        let next = self.ntwk.next_hop(node, dest)?;
+        self.ntwk.node(node)?.link_to(next).copied()
+    }
+
+    /// Sends `packet` across `link`, fragmenting it first if it's too big.
+    ///
+    /// Fragments go out back to back, so each one arrives after the ones
+    /// sent before it.
+    fn transmit(&mut self, now: u32, node: u32, link: NtwkLink, mut packet: Packet) {
+        if let Packet::Message(m) = &mut packet {
+            m.depart(now);
+        }
+        let pieces = match (packet, link.mtu()) {
+            (Packet::Message(m), Some(mtu)) if m.size() > mtu => {
+                m.fragment(mtu).into_iter().map(Packet::Message).collect()
+            }
+            (packet, _) => vec![packet],
+        };
+
+        let mut depart = now;
+        for piece in pieces {
+            depart += link.tx_time(piece.size());
+            if let Some(state) = self.nodes.get_mut(&node) {
+                state.count_forward();
+            }
+            let (lost, jitter) = self.link_effects(node, &link);
+            if lost {
+                self.drop_at(depart, node, link.to(), &piece, DropReason::LinkLoss);
+                continue;
+            }
+            self.trace.record(
+                depart,
+                TraceEvent::Transmit {
+                    node,
+                    to: link.to(),
+                    packet: (&piece).into(),
+                },
+            );
+            self.set_status(
+                &piece,
+                link.to(),
+                MsgStatus::InTransit {
+                    from: node,
+                    to: link.to(),
+                },
+            );
+            self.schedule(
+                depart + jitter,
+                EventKind::Arrive {
+                    node: link.to(),
+                    packet: piece,
+                },
+            );
+        }
+    }
+
+    /// Decides whether a packet sent from `node` across `link` is lost, and
+    /// how many ticks of jitter it picks up if it isn't.
+    fn link_effects(&mut self, node: u32, link: &NtwkLink) -> (bool, u32) {
+        if link.loss() == 0.0 && link.jitter().is_none() {
+            return (false, 0);
+        }
+        let seed = self.seed;
+        let rng = self
+            .link_rngs
+            .entry((node, link.to()))
+            .or_insert_with(|| Rng::stream(seed, Stream::Link(node, link.to())));
+
+        if link.loss() > 0.0 && rng.next_f64() < link.loss() {
+            return (true, 0);
+        }
+        let jitter = match link.jitter() {
+            Some(Jitter::Uniform { max }) => rng.next_below_or_eq(max),
+            Some(Jitter::Normal { stddev }) => (rng.next_normal() * stddev).abs().round() as u32,
+            None => 0,
+        };
+        (false, jitter)
+    }
+
+    /// Sends the next message of a traffic generator and schedules the one
+    /// after it.
+    fn generate(&mut self, now: u32, flow: usize) {
+        let gen = match self.flows.get_mut(flow) {
+            Some(gen) => gen,
+            None => return,
+        };
+        let msg = match gen.emit(now) {
+            Some(msg) => msg,
+            None => return,
+        };
+        let next = gen.next_due();
+
+        self.send(&msg);
+        if let Some(due) = next {
+            self.schedule(due, EventKind::Generate { flow });
+        }
+    }
+
+    /// Sends a copy of a broadcast out of every link of `node` except the
+    /// one it arrived on.
+    fn flood(&mut self, now: u32, node: u32, m: &MessagePacket) {
+        let links: Vec<NtwkLink> = match self.ntwk.node(node) {
+            Some(n) => n.links().to_vec(),
+            None => return,
+        };
+
+        // Copies too big for a link are fragmented by transmit, like any
+        // other message
+        for link in links.into_iter().filter(|l| Some(l.to()) != m.prev_node()) {
+            self.transmit(now, node, link, Packet::Message(m.duplicate()));
+        }
+    }
+
+    /// Delivers a broadcast the first time all of it reaches `node`.
+    ///
+    /// Returns the whole broadcast to be flooded on from `node`, or `None`
+    /// if it's a copy `node` has already seen, in which case it should be
+    /// discarded, or a fragment of one still being reassembled.
+    fn receive_broadcast(
+        &mut self,
+        now: u32,
+        node: u32,
+        m: MessagePacket,
+    ) -> Option<MessagePacket> {
+        if self.nodes.get(&node)?.has_seen(m.id(), m.nth()) {
+            return None;
+        }
+        let (m, fragments) = match m.frag() {
+            Some(_) => self.reassemble(now, node, m)?,
+            None => (m, 0),
+        };
+        // Should be okay to unwrap, checked above
+        self.nodes
+            .get_mut(&node)
+            .unwrap()
+            .mark_seen(m.id(), m.nth());
+
+        let payload = m.payload().map(|p| p.to_vec());
+        self.update_delivery(m.id(), m.nth(), node, |delivery| {
+            if delivery.set_status(MsgStatus::Delivered { tick: now }) {
+                delivery.set_hops(m.hops());
+                delivery.set_fragments(fragments);
+                delivery.set_payload(payload);
+                delivery.set_path(m.path().to_vec());
+            }
+        });
+        self.trace_delivery(now, node, m.id(), false);
+        Some(m)
+    }
+
+    /// Hands a packet to its end node, reassembling fragmented messages.
+    fn deliver(&mut self, now: u32, node: u32, packet: Packet) {
+        let m = match packet {
+            Packet::Message(m) => m,
+            Packet::Ack(a) => return self.handle_ack(now, a),
+        };
+        let (mut m, fragments) = match m.frag() {
+            Some(_) => match self.reassemble(now, node, m) {
+                Some(whole) => whole,
+                None => return,
+            },
+            None => (m, 0),
+        };
+
+        self.send_ack(now, &m);
+        let payload = m.take_payload();
+        self.update_delivery(m.id(), m.nth(), node, |delivery| {
+            if delivery.set_status(MsgStatus::Delivered { tick: now }) {
+                delivery.set_hops(m.hops());
+                delivery.set_fragments(fragments);
+                delivery.set_payload(payload);
+                delivery.set_path(m.path().to_vec());
+            }
+        });
+        self.trace_delivery(now, node, m.id(), false);
+    }
+
+    /// Adds a fragment to the ones `node` has collected of its message.
+    ///
+    /// Once every fragment is in, returns the whole message, rebuilt from
+    /// the fragment that completed it, along with the number of fragments
+    /// it came in.
+    fn reassemble(
+        &mut self,
+        now: u32,
+        node: u32,
+        mut m: MessagePacket,
+    ) -> Option<(MessagePacket, u32)> {
+        let frag = m.frag()?;
+        let key = (node, m.id(), m.nth());
+        if let Entry::Vacant(entry) = self.reassemblies.entry(key) {
+            entry.insert(Reassembly::new(frag.total()));
+            self.schedule(
+                now + REASSEMBLY_TIMEOUT,
+                EventKind::ReassemblyTimeout {
+                    node,
+                    msg_id: m.id(),
+                    nth: m.nth(),
+                },
+            );
+        }
+
+        // Should be okay to unwrap, inserted above
+        let reassembly = self.reassemblies.get_mut(&key).unwrap();
+        reassembly.add(&mut m);
+        let (received, total, fragments) = (
+            reassembly.received(),
+            reassembly.total(),
+            reassembly.fragments(),
+        );
+
+        if reassembly.is_complete() {
+            let payload = reassembly.payload();
+            self.reassemblies.remove(&key);
+            return Some((m.reassembled(payload), fragments));
+        }
+
+        self.update_delivery(m.id(), m.nth(), node, |delivery| {
+            if delivery.set_status(MsgStatus::Reassembling {
+                node,
+                received,
+                total,
+            }) {
+                delivery.set_fragments(fragments);
+                delivery.set_path(m.path().to_vec());
+            }
+        });
+        None
+    }
+
+    /// Gives up waiting for the missing fragments of a message, reporting
+    /// whatever did arrive as a partial delivery.
+    fn reassembly_timeout(&mut self, now: u32, node: u32, msg_id: u32, nth: u32) {
+        let reassembly = match self.reassemblies.remove(&(node, msg_id, nth)) {
+            Some(reassembly) => reassembly,
+            None => return,
+        };
+
+        self.update_delivery(msg_id, nth, node, |delivery| {
+            if delivery.set_status(MsgStatus::PartiallyDelivered {
+                tick: now,
+                received: reassembly.received(),
+                total: reassembly.total(),
+            }) {
+                delivery.set_fragments(reassembly.fragments());
+                delivery.set_payload(reassembly.payload());
+            }
+        });
+        self.trace_delivery(now, node, msg_id, true);
+    }
+
+    fn try_serve(&mut self, now: u32, node: u32) {
+        // Should be okay to unwrap, service only starts on known nodes
+        if self.nodes.get_mut(&node).unwrap().start_service(now) {
+            let delay = self.ntwk.node(node).map_or(0, |n| n.queue_delay());
+            self.schedule(now + delay, EventKind::ServiceDone { node });
+        }
+    }
+
+    /// Sends an ACK for `m` back from its end node, if it asked for one.
+    fn send_ack(&mut self, now: u32, m: &MessagePacket) {
+        if m.attempt().is_some() {
+            self.schedule(
+                now,
+                EventKind::Arrive {
+                    node: m.end_node(),
+                    packet: Packet::Ack(AckPacket::new(now, m)),
+                },
+            );
+        }
+    }
+
+    /// Records an ACK arriving back at the start node of its message.
+    fn handle_ack(&mut self, now: u32, ack: AckPacket) {
+        let rtt = match self.reliable.get_mut(&(ack.msg_id(), ack.nth())) {
+            Some(send) => send.ack(now, ack.attempt()),
+            None => return,
+        };
+
+        self.update_report(ack.msg_id(), ack.nth(), |report| {
+            if let Some(report) = report.ack_mut() {
+                if let Some(rtt) = rtt {
+                    report.add_rtt_sample(rtt);
+                }
+                report.set_outcome(AckOutcome::Acked { tick: now });
+            }
+        });
+    }
+
+    /// Resends a reliable message whose latest attempt went unacknowledged,
+    /// or gives up on it once its retries are spent.
+    fn retransmit_timeout(&mut self, now: u32, msg_id: u32, nth: u32, attempt: u32) {
+        let send = match self.reliable.get_mut(&(msg_id, nth)) {
+            Some(send) => send,
+            None => return,
+        };
+        // Stale timeouts from earlier attempts are ignored
+        if send.is_acked() || send.attempts() != attempt {
+            return;
+        }
+
+        if !send.has_retries_left() {
+            self.update_report(msg_id, nth, |report| {
+                if let Some(report) = report.ack_mut() {
+                    report.set_outcome(AckOutcome::Failed { tick: now });
+                }
+            });
+            return;
+        }
+
+        let packet = send.retransmit(now);
+        let (attempts, rto) = (send.attempts(), send.rto());
+        self.update_report(msg_id, nth, |report| {
+            if let Some(report) = report.ack_mut() {
+                report.set_attempts(attempts);
+            }
+        });
+        self.schedule(
+            now + rto,
+            EventKind::RetransmitTimeout {
+                msg_id,
+                nth,
+                attempt: attempts,
+            },
+        );
+        self.schedule(
+            now,
+            EventKind::Arrive {
+                node: packet.start_node(),
+                packet: Packet::Message(packet),
+            },
+        );
+    }
+
+    fn drop_packet(&mut self, now: u32, node: u32, packet: &Packet, reason: DropReason) {
+        self.drop_at(now, node, node, packet, reason);
+    }
+
+    /// Drops `packet` at `node`; `at` is the node whose delivery of a
+    /// broadcast copy it concerns, like for [`Engine::set_status`].
+    fn drop_at(&mut self, now: u32, node: u32, at: u32, packet: &Packet, reason: DropReason) {
+        if let Some(state) = self.nodes.get_mut(&node) {
+            state.count_drop();
+        }
+        if let Packet::Message(m) = packet {
+            let dest = if m.is_broadcast() { at } else { m.end_node() };
+            let status = MsgStatus::Dropped {
+                tick: now,
+                node,
+                reason,
+            };
+            self.update_delivery(m.id(), m.nth(), dest, |delivery| {
+                if delivery.set_status(status) {
+                    delivery.set_hops(m.hops());
+                    delivery.set_path(m.path().to_vec());
+                }
+            });
+        }
+        self.trace.record(
+            now,
+            TraceEvent::Drop {
+                node,
+                packet: packet.into(),
+                reason,
+            },
+        );
+    }
+
+    fn trace_delivery(&mut self, now: u32, node: u32, msg_id: u32, partial: bool) {
+        self.trace.record(
+            now,
+            TraceEvent::Deliver {
+                node,
+                packet: PacketTag::msg(msg_id),
+                partial,
+            },
+        );
+    }
+
+    /// Updates the report of the message `packet` belongs to.
+    ///
+    /// Broadcast copies update the delivery of the node `at` they concern,
+    /// every other packet updates the delivery of its end node.
+    fn set_status(&mut self, packet: &Packet, at: u32, status: MsgStatus) {
+        if let Packet::Message(m) = packet {
+            let dest = if m.is_broadcast() { at } else { m.end_node() };
+            self.update_delivery(m.id(), m.nth(), dest, |delivery| {
+                if delivery.set_status(status) {
+                    delivery.set_hops(m.hops());
+                }
+            });
+        }
+    }
+
+    fn update_delivery<F>(&self, msg_id: u32, nth: u32, dest: u32, f: F)
+    where
+        F: FnOnce(&mut Delivery),
+    {
+        self.update_report(msg_id, nth, |report| {
+            if let Some(delivery) = report.delivery_mut(dest) {
+                f(delivery);
+            }
+        });
+    }
+
+    fn update_report<F>(&self, msg_id: u32, nth: u32, f: F)
+    where
+        F: FnOnce(&mut MsgReport),
+    {
+        if let Some(report) = self.reports.find_by(|r| (msg_id, nth).cmp(&r.key())) {
+            f(&mut report.get_mut());
+        }
+    }
+}
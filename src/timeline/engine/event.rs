@@ -0,0 +1,130 @@
+use std::{cmp::Ordering, io};
+
+use crate::timeline::{
+    msg::packet::Packet,
+    snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter},
+};
+
+pub enum EventKind {
+    /// A packet reaches `node`, either from a link or from an injection.
+    Arrive { node: u32, packet: Packet },
+    /// `node` has finished servicing the packet at the head of its queue.
+    ServiceDone { node: u32 },
+    /// `node` stops waiting for the remaining fragments of the `nth`
+    /// message sent with ID `msg_id`.
+    ReassemblyTimeout { node: u32, msg_id: u32, nth: u32 },
+    /// The start node of a reliable message stops waiting for the ACK of
+    /// `attempt`.
+    RetransmitTimeout { msg_id: u32, nth: u32, attempt: u32 },
+    /// Traffic generator number `flow` sends its next message.
+    Generate { flow: usize },
+}
+
+/// An event waiting in the engine's scheduler.
+///
+/// Events are ordered by tick, then by the order they were scheduled in,
+/// so events due on the same tick are handled first-come, first-served.
+pub struct Event {
+    tick: u32,
+    seq: u64,
+    kind: EventKind,
+}
+
+impl Event {
+    pub fn new(tick: u32, seq: u64, kind: EventKind) -> Event {
+        Event { tick, seq, kind }
+    }
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+    pub fn kind(&self) -> &EventKind {
+        &self.kind
+    }
+    pub fn into_kind(self) -> EventKind {
+        self.kind
+    }
+}
+
+impl PartialEq for Event {
+    fn eq(&self, other: &Self) -> bool {
+        self.tick == other.tick && self.seq == other.seq
+    }
+}
+
+impl Eq for Event {}
+
+impl PartialOrd for Event {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Event {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.tick, self.seq).cmp(&(other.tick, other.seq))
+    }
+}
+
+impl Snap for Event {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.tag("event")?;
+        out.val(self.tick)?;
+        out.val(self.seq)?;
+        match &self.kind {
+            EventKind::Arrive { node, packet } => {
+                out.val("arrive")?;
+                out.val(node)?;
+                packet.save(out)
+            }
+            EventKind::ServiceDone { node } => {
+                out.val("serviceDone")?;
+                out.val(node)
+            }
+            EventKind::ReassemblyTimeout { node, msg_id, nth } => {
+                out.val("reassemblyTimeout")?;
+                out.val(node)?;
+                out.val(msg_id)?;
+                out.val(nth)
+            }
+            EventKind::RetransmitTimeout {
+                msg_id,
+                nth,
+                attempt,
+            } => {
+                out.val("retransmitTimeout")?;
+                out.val(msg_id)?;
+                out.val(nth)?;
+                out.val(attempt)
+            }
+            EventKind::Generate { flow } => {
+                out.val("generate")?;
+                out.val(flow)
+            }
+        }
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        inp.tag("event")?;
+        let (tick, seq) = (inp.val()?, inp.val()?);
+        let kind = match inp.word()?.as_str() {
+            "arrive" => EventKind::Arrive {
+                node: inp.val()?,
+                packet: Packet::load(inp)?,
+            },
+            "serviceDone" => EventKind::ServiceDone { node: inp.val()? },
+            "reassemblyTimeout" => EventKind::ReassemblyTimeout {
+                node: inp.val()?,
+                msg_id: inp.val()?,
+                nth: inp.val()?,
+            },
+            "retransmitTimeout" => EventKind::RetransmitTimeout {
+                msg_id: inp.val()?,
+                nth: inp.val()?,
+                attempt: inp.val()?,
+            },
+            "generate" => EventKind::Generate { flow: inp.val()? },
+            _ => return Err(SnapErrCode::BadToken),
+        };
+        Ok(Event::new(tick, seq, kind))
+    }
+}
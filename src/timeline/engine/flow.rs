@@ -0,0 +1,97 @@
+use std::io;
+
+use crate::timeline::{
+    sim::instr::{Arrivals, FlowInstr, MsgInstr},
+    snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter},
+    utils::rng::{Rng, Stream},
+};
+
+/// Runtime state of a traffic generator.
+pub struct FlowGen {
+    flow: FlowInstr,
+    sent: u32,
+    /// Arrival time of the latest message, kept unrounded so Poisson
+    /// gaps shorter than a tick still add up.
+    clock: f64,
+    rng: Option<Rng>,
+}
+
+impl FlowGen {
+    pub fn new(flow: FlowInstr, sim_seed: u64) -> FlowGen {
+        let rng = match flow.arrivals() {
+            Arrivals::Constant { .. } => None,
+            Arrivals::Poisson { seed, .. } => Some(match seed {
+                Some(seed) => Rng::new(seed),
+                None => Rng::stream(sim_seed, Stream::Flow(flow.msg_id())),
+            }),
+        };
+        FlowGen {
+            clock: flow.timestamp() as f64,
+            flow,
+            sent: 0,
+            rng,
+        }
+    }
+
+    /// Works out the tick the next message is due at, or `None` once the
+    /// generator has sent all of its messages.
+    ///
+    /// Constant flows send their first message at their own timestamp,
+    /// Poisson flows one random gap after it.
+    pub fn next_due(&mut self) -> Option<u32> {
+        if self.sent >= self.flow.count() {
+            return None;
+        }
+        match (self.flow.arrivals(), self.rng.as_mut()) {
+            (Arrivals::Constant { interval }, _) => {
+                let due = self.flow.timestamp() as u64 + self.sent as u64 * interval as u64;
+                due.try_into().ok()
+            }
+            (Arrivals::Poisson { rate, .. }, Some(rng)) => {
+                self.clock += rng.next_exp(rate);
+                (self.clock < u32::MAX as f64).then_some(self.clock as u32)
+            }
+            (Arrivals::Poisson { .. }, None) => None,
+        }
+    }
+
+    /// Builds the next message of the flow, sent at `now`, or `None` if
+    /// the flow has run out of message IDs.
+    pub fn emit(&mut self, now: u32) -> Option<MsgInstr> {
+        let msg = self.flow.message(now, self.sent)?;
+        self.sent += 1;
+        Some(msg)
+    }
+}
+
+impl Snap for FlowGen {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.tag("flow")?;
+        out.val(self.sent)?;
+        out.val(self.clock)?;
+        match &self.rng {
+            Some(rng) => {
+                out.val("+")?;
+                rng.save(out)?;
+            }
+            None => out.val("-")?,
+        }
+        self.flow.save(out)
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        inp.tag("flow")?;
+        let (sent, clock) = (inp.val()?, inp.val()?);
+        let rng = match inp.word()?.as_str() {
+            "+" => Some(Rng::load(inp)?),
+            "-" => None,
+            _ => return Err(SnapErrCode::BadToken),
+        };
+        Ok(FlowGen {
+            flow: FlowInstr::load(inp)?,
+            sent,
+            clock,
+            rng,
+        })
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    io,
+};
+
+use crate::timeline::{
+    msg::packet::Packet,
+    snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter},
+};
+
+/// Running totals of what a node has done, for the metrics export.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NodeStats {
+    forwarded: u32,
+    max_depth: usize,
+    drops: u32,
+    served: u32,
+    /// Ticks every served packet spent in the queue, added up.
+    total_wait: u64,
+}
+
+impl NodeStats {
+    /// Packets the node sent on towards another node.
+    pub fn forwarded(&self) -> u32 {
+        self.forwarded
+    }
+    /// Most packets held at once, including the one being serviced.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+    pub fn drops(&self) -> u32 {
+        self.drops
+    }
+    /// Average ticks a packet waited in the queue before its service
+    /// started, or `None` if the node never serviced anything.
+    pub fn mean_wait(&self) -> Option<f64> {
+        (self.served > 0).then(|| self.total_wait as f64 / self.served as f64)
+    }
+}
+
+/// Runtime state of a single network node.
+#[derive(Default)]
+pub struct NodeState {
+    /// Waiting packets, with the tick each one joined the queue.
+    queue: VecDeque<(u32, Packet)>,
+    in_service: Option<Packet>,
+    queued_bytes: u32,
+    /// ID and nth of the broadcasts that have already passed through this
+    /// node.
+    seen: HashSet<(u32, u32)>,
+    stats: NodeStats,
+}
+
+impl NodeState {
+    pub fn is_busy(&self) -> bool {
+        self.in_service.is_some()
+    }
+
+    /// Bytes held by the node, including the packet being serviced.
+    pub fn queued_bytes(&self) -> u32 {
+        self.queued_bytes
+    }
+
+    pub fn in_service(&self) -> Option<&Packet> {
+        self.in_service.as_ref()
+    }
+
+    /// Packets waiting behind the one in service, oldest first.
+    pub fn waiting(&self) -> impl Iterator<Item = &Packet> {
+        self.queue.iter().map(|(_, packet)| packet)
+    }
+
+    /// Number of packets held, including the one in service.
+    pub fn depth(&self) -> usize {
+        self.queue.len() + usize::from(self.is_busy())
+    }
+
+    pub fn stats(&self) -> NodeStats {
+        self.stats
+    }
+
+    pub fn has_seen(&self, msg_id: u32, nth: u32) -> bool {
+        self.seen.contains(&(msg_id, nth))
+    }
+
+    /// Remembers a broadcast, returning `false` if the node had already seen it.
+    pub fn mark_seen(&mut self, msg_id: u32, nth: u32) -> bool {
+        self.seen.insert((msg_id, nth))
+    }
+
+    pub fn push(&mut self, now: u32, packet: Packet) {
+        self.queued_bytes += packet.size();
+        self.queue.push_back((now, packet));
+        self.stats.max_depth = self.stats.max_depth.max(self.depth());
+    }
+
+    /// Moves the packet at the head of the queue into service.
+    ///
+    /// Returns `false` if the queue was empty or the node was already busy.
+    pub fn start_service(&mut self, now: u32) -> bool {
+        if self.is_busy() {
+            return false;
+        }
+        let (enqueued, packet) = match self.queue.pop_front() {
+            Some(entry) => entry,
+            None => return false,
+        };
+        self.stats.served += 1;
+        self.stats.total_wait += (now - enqueued) as u64;
+        self.in_service = Some(packet);
+        true
+    }
+
+    pub fn finish_service(&mut self) -> Option<Packet> {
+        let packet = self.in_service.take()?;
+        self.queued_bytes -= packet.size();
+        Some(packet)
+    }
+
+    pub fn count_forward(&mut self) {
+        self.stats.forwarded += 1;
+    }
+
+    pub fn count_drop(&mut self) {
+        self.stats.drops += 1;
+    }
+}
+
+impl Snap for NodeState {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        let stats = &self.stats;
+        out.val(stats.forwarded)?;
+        out.val(stats.max_depth)?;
+        out.val(stats.drops)?;
+        out.val(stats.served)?;
+        out.val(stats.total_wait)?;
+
+        let mut seen: Vec<&(u32, u32)> = self.seen.iter().collect();
+        seen.sort();
+        out.tag("seen")?;
+        out.list(seen.into_iter())?;
+
+        out.tag("serving")?;
+        match &self.in_service {
+            Some(packet) => {
+                out.val("+")?;
+                packet.save(out)?;
+            }
+            None => out.val("-")?,
+        }
+        out.tag("queue")?;
+        out.val(self.queue.len())?;
+        for (enqueued, packet) in self.queue.iter() {
+            out.tag("queued")?;
+            out.val(enqueued)?;
+            packet.save(out)?;
+        }
+        Ok(())
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        let stats = NodeStats {
+            forwarded: inp.val()?,
+            max_depth: inp.val()?,
+            drops: inp.val()?,
+            served: inp.val()?,
+            total_wait: inp.val()?,
+        };
+
+        inp.tag("seen")?;
+        let seen: Vec<(u32, u32)> = inp.list()?;
+
+        inp.tag("serving")?;
+        let in_service = match inp.word()?.as_str() {
+            "+" => Some(Packet::load(inp)?),
+            "-" => None,
+            _ => return Err(SnapErrCode::BadToken),
+        };
+
+        inp.tag("queue")?;
+        let count: usize = inp.val()?;
+        let mut queue = VecDeque::with_capacity(count);
+        for _ in 0..count {
+            inp.tag("queued")?;
+            queue.push_back((inp.val()?, Packet::load(inp)?));
+        }
+
+        let queued_bytes = in_service
+            .iter()
+            .chain(queue.iter().map(|(_, p)| p))
+            .map(|p| p.size())
+            .sum();
+        Ok(NodeState {
+            queue,
+            in_service,
+            queued_bytes,
+            seen: seen.into_iter().collect(),
+            stats,
+        })
+    }
+}
@@ -0,0 +1,122 @@
+use std::io;
+
+use crate::timeline::{
+    msg::packet::MessagePacket,
+    snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter},
+};
+
+/// Fragments of one message collected at its end node.
+///
+/// Copies of a message can be cut differently on the way, by links with
+/// different MTUs or a route that changed between retransmissions, so
+/// fragments are tracked by the bytes they cover rather than by offset.
+pub struct Reassembly {
+    total: u32,
+    /// Fragments that brought bytes not already received.
+    fragments: u32,
+    /// Byte ranges received so far, sorted and not touching each other.
+    covered: Vec<(u32, u32)>,
+    /// Payload bytes received so far, each at its offset in the message.
+    payload: Option<Vec<u8>>,
+}
+
+impl Reassembly {
+    pub fn new(total: u32) -> Reassembly {
+        Reassembly {
+            total,
+            fragments: 0,
+            covered: Vec::new(),
+            payload: None,
+        }
+    }
+
+    /// Stores a fragment, ignoring it if every byte it covers already
+    /// arrived. The fragment's payload moves into the reassembly.
+    pub fn add(&mut self, packet: &mut MessagePacket) {
+        let start = packet.frag().map_or(0, |f| f.offset()).min(self.total);
+        let end = start.saturating_add(packet.size()).min(self.total);
+        if !self.cover(start, end) {
+            return;
+        }
+        self.fragments += 1;
+
+        if let Some(slice) = packet.take_payload() {
+            let (from, to) = (start as usize, start as usize + slice.len());
+            let payload = self.payload.get_or_insert_with(Vec::new);
+            if payload.len() < to {
+                payload.resize(to, 0);
+            }
+            payload[from..to].copy_from_slice(&slice);
+        }
+    }
+
+    /// Marks `start..end` as received, returning whether any of it is new.
+    fn cover(&mut self, start: u32, end: u32) -> bool {
+        let before = self.received();
+        self.covered.push((start, end));
+        self.covered.sort_unstable();
+
+        let mut merged: Vec<(u32, u32)> = Vec::with_capacity(self.covered.len());
+        for &(start, end) in &self.covered {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ if start < end => merged.push((start, end)),
+                _ => (),
+            }
+        }
+        self.covered = merged;
+        self.received() > before
+    }
+
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    /// Number of distinct bytes received.
+    pub fn received(&self) -> u32 {
+        self.covered.iter().map(|(start, end)| end - start).sum()
+    }
+
+    pub fn fragments(&self) -> u32 {
+        self.fragments
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.covered == [(0, self.total)]
+    }
+
+    /// The payload received so far, up to the first byte still missing.
+    pub fn payload(&self) -> Option<Vec<u8>> {
+        let received = match self.covered.first() {
+            Some(&(0, end)) => end as usize,
+            _ => 0,
+        };
+        self.payload
+            .as_ref()
+            .map(|payload| payload[..payload.len().min(received)].to_vec())
+    }
+}
+
+impl Snap for Reassembly {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.val(self.total)?;
+        out.val(self.fragments)?;
+        out.val(self.covered.len())?;
+        for (start, end) in self.covered.iter() {
+            out.val(start)?;
+            out.val(end)?;
+        }
+        out.bytes(self.payload.as_deref())
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        let mut reassembly = Reassembly::new(inp.val()?);
+        reassembly.fragments = inp.val()?;
+        let count: usize = inp.val()?;
+        for _ in 0..count {
+            reassembly.covered.push((inp.val()?, inp.val()?));
+        }
+        reassembly.payload = inp.bytes()?;
+        Ok(reassembly)
+    }
+}
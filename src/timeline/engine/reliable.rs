@@ -0,0 +1,85 @@
+use std::io;
+
+use crate::timeline::{
+    msg::packet::MessagePacket,
+    sim::instr::Reliability,
+    snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter},
+};
+
+/// Source-side bookkeeping for a message sent in reliable mode.
+pub struct ReliableSend {
+    /// Copy of the message as it first left its start node.
+    template: MessagePacket,
+    /// Tick each attempt was sent at; attempt `n` is at index `n - 1`.
+    sent_at: Vec<u32>,
+    rto: u32,
+    retries: u32,
+    acked: bool,
+}
+
+impl ReliableSend {
+    pub fn new(template: MessagePacket, reliability: Reliability, now: u32) -> ReliableSend {
+        ReliableSend {
+            template,
+            sent_at: vec![now],
+            rto: reliability.rto(),
+            retries: reliability.retries(),
+            acked: false,
+        }
+    }
+
+    pub fn start_node(&self) -> u32 {
+        self.template.start_node()
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.sent_at.len() as u32
+    }
+
+    pub fn rto(&self) -> u32 {
+        self.rto
+    }
+
+    pub fn is_acked(&self) -> bool {
+        self.acked
+    }
+
+    pub fn has_retries_left(&self) -> bool {
+        self.attempts() <= self.retries
+    }
+
+    /// Marks the message acknowledged and returns the round trip time of
+    /// the attempt the ACK answers.
+    pub fn ack(&mut self, now: u32, attempt: u32) -> Option<u32> {
+        self.acked = true;
+        let sent = self.sent_at.get((attempt as usize).checked_sub(1)?)?;
+        Some(now - sent)
+    }
+
+    /// Builds the next copy of the message and backs off the timeout.
+    pub fn retransmit(&mut self, now: u32) -> MessagePacket {
+        self.sent_at.push(now);
+        self.rto = self.rto.saturating_mul(2);
+        self.template.resend(now, self.attempts())
+    }
+}
+
+impl Snap for ReliableSend {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.list(self.sent_at.iter())?;
+        out.val(self.rto)?;
+        out.val(self.retries)?;
+        out.val(self.acked)?;
+        self.template.save(out)
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        Ok(ReliableSend {
+            sent_at: inp.list()?,
+            rto: inp.val()?,
+            retries: inp.val()?,
+            acked: inp.val()?,
+            template: MessagePacket::load(inp)?,
+        })
+    }
+}
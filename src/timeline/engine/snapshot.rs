@@ -0,0 +1,148 @@
+use std::{cmp::Reverse, collections::HashMap, io};
+
+use super::{
+    event::Event, flow::FlowGen, node_state::NodeState, reassembly::Reassembly,
+    reliable::ReliableSend, Engine,
+};
+use crate::timeline::{
+    msg::{avl_tree::AvlTree, report::MsgReport},
+    ntwk::Ntwk,
+    snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter},
+    trace::Trace,
+    utils::rng::Rng,
+};
+
+impl<'a> Engine<'a> {
+    /// Writes everything the engine keeps between ticks.
+    ///
+    /// Hash maps and the scheduler are written in sorted order, so saving
+    /// the same state twice gives the same file. The trace sink isn't
+    /// saved; a restored run writes to whichever trace it's given.
+    pub fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.tag("engine")?;
+        out.val(self.seed)?;
+        out.val(self.next_seq)?;
+        out.val(self.handled)?;
+
+        out.tag("nodes")?;
+        out.val(self.nodes.len())?;
+        for (id, state) in self.nodes.iter() {
+            out.tag("state")?;
+            out.val(id)?;
+            state.save(out)?;
+        }
+
+        let mut reassemblies: Vec<_> = self.reassemblies.iter().collect();
+        reassemblies.sort_by_key(|(&key, _)| key);
+        out.tag("reassemblies")?;
+        out.val(reassemblies.len())?;
+        for (&(node, msg_id, nth), reassembly) in reassemblies {
+            out.tag("reassembly")?;
+            out.val(node)?;
+            out.val(msg_id)?;
+            out.val(nth)?;
+            reassembly.save(out)?;
+        }
+
+        let mut reliable: Vec<_> = self.reliable.iter().collect();
+        reliable.sort_by_key(|(&key, _)| key);
+        out.tag("reliable")?;
+        out.val(reliable.len())?;
+        for (&(msg_id, nth), send) in reliable {
+            out.tag("send")?;
+            out.val(msg_id)?;
+            out.val(nth)?;
+            send.save(out)?;
+        }
+
+        out.tag("flows")?;
+        out.list(self.flows.iter())?;
+
+        let mut link_rngs: Vec<_> = self.link_rngs.iter().collect();
+        link_rngs.sort_by_key(|(&key, _)| key);
+        out.tag("linkRngs")?;
+        out.val(link_rngs.len())?;
+        for (&(from, to), rng) in link_rngs {
+            out.tag("linkRng")?;
+            out.val(from)?;
+            out.val(to)?;
+            rng.save(out)?;
+        }
+
+        let mut events: Vec<&Event> = self.events.iter().map(|Reverse(e)| e).collect();
+        events.sort();
+        out.tag("events")?;
+        out.list(events.into_iter())?;
+
+        let reports: Vec<_> = self.reports.iter().collect();
+        out.tag("reports")?;
+        out.val(reports.len())?;
+        for report in reports {
+            report.get().save(out)?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds an engine saved by [`Engine::save`], running on `ntwk`.
+    pub fn load(ntwk: &'a Ntwk, inp: &mut SnapReader) -> Result<Engine<'a>, SnapErrCode> {
+        inp.tag("engine")?;
+        let mut engine = Engine::new(ntwk, inp.val()?);
+        engine.next_seq = inp.val()?;
+        engine.handled = inp.val()?;
+
+        inp.tag("nodes")?;
+        let count: usize = inp.val()?;
+        for _ in 0..count {
+            inp.tag("state")?;
+            let id = inp.val()?;
+            engine.nodes.insert(id, NodeState::load(inp)?);
+        }
+
+        inp.tag("reassemblies")?;
+        let count: usize = inp.val()?;
+        for _ in 0..count {
+            inp.tag("reassembly")?;
+            let key = (inp.val()?, inp.val()?, inp.val()?);
+            engine.reassemblies.insert(key, Reassembly::load(inp)?);
+        }
+
+        inp.tag("reliable")?;
+        let count: usize = inp.val()?;
+        for _ in 0..count {
+            inp.tag("send")?;
+            let key = (inp.val()?, inp.val()?);
+            engine.reliable.insert(key, ReliableSend::load(inp)?);
+        }
+
+        inp.tag("flows")?;
+        engine.flows = inp.list::<FlowGen>()?;
+
+        inp.tag("linkRngs")?;
+        let count: usize = inp.val()?;
+        let mut link_rngs = HashMap::with_capacity(count);
+        for _ in 0..count {
+            inp.tag("linkRng")?;
+            let key = (inp.val()?, inp.val()?);
+            link_rngs.insert(key, Rng::load(inp)?);
+        }
+        engine.link_rngs = link_rngs;
+
+        inp.tag("events")?;
+        engine.events = inp.list::<Event>()?.into_iter().map(Reverse).collect();
+
+        inp.tag("reports")?;
+        let count: usize = inp.val()?;
+        let mut reports = AvlTree::new();
+        for _ in 0..count {
+            let report = MsgReport::load(inp)?;
+            // Every message sent so far has a report, so they tell how
+            // many times each ID was used
+            let used = engine.ids_used.entry(report.id()).or_insert(0);
+            *used = (*used).max(report.nth());
+            reports.insert(report);
+        }
+        engine.reports = reports;
+        engine.trace = Trace::none();
+        Ok(engine)
+    }
+}
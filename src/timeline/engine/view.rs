@@ -0,0 +1,196 @@
+use std::io::{self, Write};
+
+use super::{
+    event::{Event, EventKind},
+    Engine,
+};
+use crate::timeline::trace::PacketTag;
+
+impl Engine<'_> {
+    /// Writes a table of what every node holds and what's on the links,
+    /// as of the end of `tick`.
+    ///
+    /// ```text
+    /// t=7
+    ///   node  depth  in service  waiting
+    ///      1      2  msg 3       ack 1
+    ///      4      0  -           -
+    ///   on links: msg 2 9->8 (t=12)
+    /// ```
+    pub fn write_tick_table<W: Write>(&self, out: &mut W, tick: u32) -> io::Result<()> {
+        writeln!(out, "t={tick}")?;
+        writeln!(
+            out,
+            "  {:>4}  {:>5}  {:<10}  waiting",
+            "node", "depth", "in service"
+        )?;
+        for (id, state) in self.nodes.iter() {
+            let serving = state
+                .in_service()
+                .map_or("-".to_string(), |p| PacketTag::from(p).to_string());
+            let waiting: Vec<String> = state
+                .waiting()
+                .map(|p| PacketTag::from(p).to_string())
+                .collect();
+            let waiting = match waiting.is_empty() {
+                true => "-".to_string(),
+                false => waiting.join(", "),
+            };
+            writeln!(
+                out,
+                "  {id:>4}  {:>5}  {serving:<10}  {waiting}",
+                state.depth()
+            )?;
+        }
+
+        let mut in_transit: Vec<(u32, String)> = self
+            .events
+            .iter()
+            .filter_map(|event| match event.0.kind() {
+                // Injections arrive at the node they're already on
+                EventKind::Arrive { node, packet } if *node != packet.current_node() => {
+                    let tag = PacketTag::from(packet);
+                    let from = packet.current_node();
+                    let due = event.0.tick();
+                    Some((due, format!("{tag} {from}->{node} (t={due})")))
+                }
+                _ => None,
+            })
+            .collect();
+        if !in_transit.is_empty() {
+            in_transit.sort();
+            let in_transit: Vec<String> = in_transit.into_iter().map(|(_, s)| s).collect();
+            writeln!(out, "  on links: {}", in_transit.join(", "))?;
+        }
+        Ok(())
+    }
+
+    /// Number of events handled so far, to tell busy ticks from idle ones.
+    pub fn events_handled(&self) -> u64 {
+        self.handled
+    }
+
+    /// Writes what `node` holds and what it has done so far.
+    ///
+    /// Returns `false` if there is no such node.
+    pub fn write_node<W: Write>(&self, out: &mut W, node: u32) -> io::Result<bool> {
+        let state = match self.nodes.get(&node) {
+            Some(state) => state,
+            None => return Ok(false),
+        };
+        let stats = state.stats();
+        writeln!(
+            out,
+            "node {node}  depth {}  bytes {}  forwarded {}  drops {}",
+            state.depth(),
+            state.queued_bytes(),
+            stats.forwarded(),
+            stats.drops()
+        )?;
+        let serving = state
+            .in_service()
+            .map_or("-".to_string(), |p| PacketTag::from(p).to_string());
+        writeln!(out, "  in service: {serving}")?;
+        for packet in state.waiting() {
+            writeln!(
+                out,
+                "  waiting: {} for node {}",
+                PacketTag::from(packet),
+                packet.end_node()
+            )?;
+        }
+        Ok(true)
+    }
+
+    /// Writes where every packet of message `id` is right now: held by a
+    /// node, on a link, or waiting to be reassembled.
+    pub fn write_packet<W: Write>(&self, out: &mut W, id: u32) -> io::Result<()> {
+        let mut found = false;
+        for (node, state) in self.nodes.iter() {
+            let held = state
+                .in_service()
+                .map(|p| (p, "in service"))
+                .into_iter()
+                .chain(state.waiting().map(|p| (p, "waiting")));
+            for (packet, what) in held {
+                let tag = PacketTag::from(packet);
+                if tag.id() == id {
+                    writeln!(out, "{tag} {what} at node {node}")?;
+                    found = true;
+                }
+            }
+        }
+
+        let mut events: Vec<&Event> = self.events.iter().map(|event| &event.0).collect();
+        events.sort();
+        for event in events {
+            let (node, packet) = match event.kind() {
+                EventKind::Arrive { node, packet } => (*node, packet),
+                _ => continue,
+            };
+            let tag = PacketTag::from(packet);
+            if tag.id() != id {
+                continue;
+            }
+            let (from, due) = (packet.current_node(), event.tick());
+            match node == from {
+                true => writeln!(out, "{tag} injected at node {node} (t={due})")?,
+                false => writeln!(out, "{tag} on link {from}->{node} (t={due})")?,
+            }
+            found = true;
+        }
+
+        let mut reassembling: Vec<u32> = self
+            .reassemblies
+            .keys()
+            .filter(|(_, msg_id, _)| *msg_id == id)
+            .map(|(node, _, _)| *node)
+            .collect();
+        reassembling.sort();
+        for node in reassembling {
+            writeln!(out, "msg {id} being reassembled at node {node}")?;
+            found = true;
+        }
+
+        if !found {
+            writeln!(out, "msg {id}: no packets in the network")?;
+        }
+        Ok(())
+    }
+
+    /// Whether anything due at or before `tick` involves message `id`.
+    pub fn msg_due(&self, tick: u32, id: u32) -> bool {
+        self.due(tick).any(|(_, msg_id)| msg_id == Some(id))
+    }
+
+    /// Whether anything due at or before `tick` happens at `node`.
+    pub fn node_due(&self, tick: u32, node: u32) -> bool {
+        self.due(tick).any(|(at, _)| at == Some(node))
+    }
+
+    /// Node and message of every event due at or before `tick`, where the
+    /// event has them.
+    fn due(&self, tick: u32) -> impl Iterator<Item = (Option<u32>, Option<u32>)> + '_ {
+        self.events
+            .iter()
+            .filter(move |event| event.0.tick() <= tick)
+            .map(|event| match event.0.kind() {
+                EventKind::Arrive { node, packet } => {
+                    (Some(*node), Some(PacketTag::from(packet).id()))
+                }
+                EventKind::ServiceDone { node } => {
+                    let serving = self.nodes.get(node).and_then(|state| state.in_service());
+                    (Some(*node), serving.map(|p| PacketTag::from(p).id()))
+                }
+                EventKind::ReassemblyTimeout { node, msg_id, .. } => (Some(*node), Some(*msg_id)),
+                EventKind::RetransmitTimeout { msg_id, nth, .. } => {
+                    let start = self
+                        .reliable
+                        .get(&(*msg_id, *nth))
+                        .map(|send| send.start_node());
+                    (start, Some(*msg_id))
+                }
+                EventKind::Generate { .. } => (None, None),
+            })
+    }
+}
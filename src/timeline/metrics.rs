@@ -0,0 +1,81 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use crate::timeline::{
+    engine::Engine,
+    msg::report::{Delivery, MsgKind, MsgReport, MsgStatus},
+};
+
+/// Writes the end-of-simulation metrics as two CSV files,
+/// `<prefix>.messages.csv` and `<prefix>.nodes.csv`.
+///
+/// The messages file has one row per message and destination, so a
+/// multicast or broadcast gets a row for each node it was sent to. The
+/// nodes file has one row per node.
+pub fn write_csv(prefix: &str, engine: &Engine) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(format!("{prefix}.messages.csv"))?);
+    writeln!(out, "id,kind,src,dst,injected,delivered,hops,path,outcome")?;
+    for report in engine.reports().iter() {
+        let report = report.get();
+        for (dest, delivery) in report.deliveries() {
+            write_message_row(&mut out, &report, dest, delivery)?;
+        }
+    }
+    out.flush()?;
+
+    let mut out = BufWriter::new(File::create(format!("{prefix}.nodes.csv"))?);
+    writeln!(
+        out,
+        "node,forwarded,max_queue_depth,drops,mean_queueing_delay"
+    )?;
+    for (node, stats) in engine.node_stats() {
+        let mean_wait = stats
+            .mean_wait()
+            .map_or(String::new(), |wait| format!("{wait:.2}"));
+        writeln!(
+            out,
+            "{node},{},{},{},{mean_wait}",
+            stats.forwarded(),
+            stats.max_depth(),
+            stats.drops()
+        )?;
+    }
+    out.flush()
+}
+
+fn write_message_row<W: Write>(
+    out: &mut W,
+    report: &MsgReport,
+    dest: u32,
+    delivery: &Delivery,
+) -> io::Result<()> {
+    let kind = match report.kind() {
+        MsgKind::Unicast => "msg",
+        MsgKind::Multicast => "mcast",
+        MsgKind::Broadcast => "bcast",
+    };
+    let (delivered, outcome) = match delivery.status() {
+        MsgStatus::Delivered { tick } => (Some(tick), "delivered".to_string()),
+        MsgStatus::PartiallyDelivered { tick, .. } => (Some(tick), "partial".to_string()),
+        MsgStatus::Dropped { reason, .. } => (None, format!("dropped ({reason})")),
+        MsgStatus::NotReached => (None, "not reached".to_string()),
+        MsgStatus::Queued { .. } | MsgStatus::InTransit { .. } | MsgStatus::Reassembling { .. } => {
+            (None, "in flight".to_string())
+        }
+    };
+    let delivered = delivered.map_or(String::new(), |tick| tick.to_string());
+    // Space separated, so the path stays a single CSV field
+    let path: Vec<String> = delivery.path().iter().map(|hop| hop.to_string()).collect();
+
+    writeln!(
+        out,
+        "{},{kind},{},{dest},{},{delivered},{},{},{outcome}",
+        report.id(),
+        report.start_node(),
+        report.injected_at(),
+        delivery.hops(),
+        path.join(" ")
+    )
+}
@@ -1,2 +1,3 @@
 pub(crate) mod avl_tree;
 pub(super) mod packet;
+pub(super) mod report;
@@ -1,42 +1,148 @@
 use self::{tree_node::TreeNode, tree_node_wrapper::TreeNodeWrapper};
-use std::{cell::RefCell, fmt::Display, ops::Deref, rc::Rc};
+use std::{cell::RefCell, cmp::Ordering, fmt::Display, ops::Deref, rc::Rc};
 
 mod tree_node;
 mod tree_node_wrapper;
 
-pub struct AvlTree<'a, T: PartialOrd + PartialEq + Display> {
-    root: Option<Rc<RefCell<TreeNode<'a, T>>>>,
+type Link<T> = Rc<RefCell<TreeNode<T>>>;
+
+pub struct AvlTree<T: PartialOrd + PartialEq + Display> {
+    root: Option<Link<T>>,
     height: usize,
 }
 
-impl<'a, T: PartialOrd + PartialEq + Display> AvlTree<'a, T> {
+impl<T: PartialOrd + PartialEq + Display> AvlTree<T> {
     pub fn new() -> Self {
         AvlTree {
             root: None,
             height: 0,
         }
     }
-    pub fn iter(&'a self) -> TreeAsIter<'a, T> {
-        let curr = if let Some(root) = self.root.as_ref() {
-            Some(Rc::clone(root))
-        } else {
-            None
-        };
+
+    pub fn iter(&self) -> TreeAsIter<T> {
+        let curr = self.root.as_ref().map(Rc::clone);
 
         TreeAsIter {
             parents: Vec::with_capacity(self.height),
             curr,
         }
     }
+
+    /// Inserts `item` into the tree, rebalancing on the way back up.
+    ///
+    /// If the tree already holds an item that compares equal to `item`,
+    /// that item is replaced and the tree keeps its shape.
+    pub fn insert(&mut self, item: T) {
+        let root = self.root.take();
+        let root = insert_helper(root, item);
+        self.height = root.borrow().height();
+        self.root = Some(root);
+    }
+
+    /// Searches the tree using `cmp`, which should report how the
+    /// wanted item orders relative to the item it is handed.
+    pub fn find_by<F>(&self, cmp: F) -> Option<TreeNodeWrapper<T>>
+    where
+        F: Fn(&T) -> Ordering,
+    {
+        let mut curr = self.root.as_ref().map(Rc::clone);
+        while let Some(node) = curr {
+            let ord = cmp(node.borrow().get());
+            curr = match ord {
+                Ordering::Less => node.borrow().left.as_ref().map(Rc::clone),
+                Ordering::Greater => node.borrow().right.as_ref().map(Rc::clone),
+                Ordering::Equal => return Some(TreeNodeWrapper { guard: node }),
+            };
+        }
+        None
+    }
+}
+
+impl<T: PartialOrd + PartialEq + Display> Default for AvlTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Inserts `item` below `node` and returns the (possibly new) subtree root.
+fn insert_helper<T: PartialOrd>(node: Option<Link<T>>, item: T) -> Link<T> {
+    let node = match node {
+        Some(node) => node,
+        None => return Rc::new(RefCell::new(TreeNode::new(item))),
+    };
+
+    let ord = item.partial_cmp(node.borrow().get());
+    match ord {
+        Some(Ordering::Less) => {
+            let left = node.borrow_mut().left.take();
+            node.borrow_mut().left = Some(insert_helper(left, item));
+        }
+        Some(Ordering::Greater) => {
+            let right = node.borrow_mut().right.take();
+            node.borrow_mut().right = Some(insert_helper(right, item));
+        }
+        _ => {
+            node.borrow_mut().replace(item);
+            return node;
+        }
+    }
+
+    node.borrow_mut().update_height();
+    rebalance(node)
+}
+
+fn rebalance<T: PartialOrd>(node: Link<T>) -> Link<T> {
+    let balance = node.borrow().balance();
+    if balance > 1 {
+        let left = node.borrow_mut().left.take().unwrap();
+        let left = if left.borrow().balance() < 0 {
+            rotate_left(left)
+        } else {
+            left
+        };
+        node.borrow_mut().left = Some(left);
+        rotate_right(node)
+    } else if balance < -1 {
+        let right = node.borrow_mut().right.take().unwrap();
+        let right = if right.borrow().balance() > 0 {
+            rotate_right(right)
+        } else {
+            right
+        };
+        node.borrow_mut().right = Some(right);
+        rotate_left(node)
+    } else {
+        node
+    }
+}
+
+fn rotate_right<T: PartialOrd>(node: Link<T>) -> Link<T> {
+    // Should be okay to unwrap, only called when the left side is heavier
+    let pivot = node.borrow_mut().left.take().unwrap();
+    node.borrow_mut().left = pivot.borrow_mut().right.take();
+    node.borrow_mut().update_height();
+    pivot.borrow_mut().right = Some(node);
+    pivot.borrow_mut().update_height();
+    pivot
+}
+
+fn rotate_left<T: PartialOrd>(node: Link<T>) -> Link<T> {
+    // Should be okay to unwrap, only called when the right side is heavier
+    let pivot = node.borrow_mut().right.take().unwrap();
+    node.borrow_mut().right = pivot.borrow_mut().left.take();
+    node.borrow_mut().update_height();
+    pivot.borrow_mut().left = Some(node);
+    pivot.borrow_mut().update_height();
+    pivot
 }
 
-pub struct TreeAsIter<'a, T: PartialOrd + PartialEq + Display> {
-    parents: Vec<Rc<RefCell<TreeNode<'a, T>>>>,
-    curr: Option<Rc<RefCell<TreeNode<'a, T>>>>,
+pub struct TreeAsIter<T: PartialOrd + PartialEq + Display> {
+    parents: Vec<Link<T>>,
+    curr: Option<Link<T>>,
 }
 
-impl<'a, T: PartialOrd + PartialEq + Display> Iterator for TreeAsIter<'a, T> {
-    type Item = TreeNodeWrapper<'a, T>;
+impl<T: PartialOrd + PartialEq + Display> Iterator for TreeAsIter<T> {
+    type Item = TreeNodeWrapper<T>;
 
     fn next(&mut self) -> Option<Self::Item> {
         while let Some(traverse) = if let Some(item) = self.curr.as_ref() {
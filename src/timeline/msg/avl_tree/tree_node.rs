@@ -1,29 +1,55 @@
-use std::{
-    cell::RefCell,
-    rc::{Rc, Weak},
-};
+use std::{cell::RefCell, rc::Rc};
 
-pub struct TreeNode<'a, T>
+pub struct TreeNode<T>
 where
     T: PartialOrd,
 {
-    item: &'a T,
-    pub left: Option<Rc<RefCell<TreeNode<'a, T>>>>,
-    pub right: Option<Rc<RefCell<TreeNode<'a, T>>>>,
-    pub parent: Option<Weak<RefCell<TreeNode<'a, T>>>>,
+    item: T,
+    height: usize,
+    pub left: Option<Rc<RefCell<TreeNode<T>>>>,
+    pub right: Option<Rc<RefCell<TreeNode<T>>>>,
 }
 
-impl<'a, T: PartialOrd> TreeNode<'a, T> {
-    pub fn new(data: &'a T) -> Self {
+impl<T: PartialOrd> TreeNode<T> {
+    pub fn new(data: T) -> Self {
         TreeNode {
             item: data,
+            height: 1,
             left: None,
             right: None,
-            parent: None,
         }
     }
 
     pub fn get(&self) -> &T {
         &self.item
     }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.item
+    }
+
+    pub fn replace(&mut self, data: T) {
+        self.item = data;
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Recomputes this node's height from the heights of its children.
+    pub fn update_height(&mut self) {
+        self.height = 1 + usize::max(height_of(&self.left), height_of(&self.right));
+    }
+
+    /// Height of the left subtree minus the height of the right subtree.
+    pub fn balance(&self) -> isize {
+        height_of(&self.left) as isize - height_of(&self.right) as isize
+    }
+}
+
+pub fn height_of<T: PartialOrd>(node: &Option<Rc<RefCell<TreeNode<T>>>>) -> usize {
+    match node {
+        Some(n) => n.borrow().height(),
+        None => 0,
+    }
 }
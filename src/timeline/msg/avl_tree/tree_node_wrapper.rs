@@ -1,18 +1,22 @@
 use std::{
-    cell::{Ref, RefCell},
+    cell::{Ref, RefCell, RefMut},
     fmt::Display,
-    ops::Deref,
+    ops::{Deref, DerefMut},
     rc::Rc,
 };
 
 use super::tree_node::TreeNode;
 
-pub struct TreeNodeWrapper<'a, T: PartialOrd + PartialEq + Display> {
-    pub guard: Rc<RefCell<TreeNode<'a, T>>>,
+pub struct TreeNodeWrapper<T: PartialOrd + PartialEq + Display> {
+    pub guard: Rc<RefCell<TreeNode<T>>>,
 }
 
-impl<'a, T: PartialOrd + PartialEq + Display> TreeNodeWrapper<'a, T> {
+impl<T: PartialOrd + PartialEq + Display> TreeNodeWrapper<T> {
     pub fn get(&self) -> impl Display + Deref<Target = T> + '_ {
         Ref::map(self.guard.borrow(), |item| item.get())
     }
+
+    pub fn get_mut(&self) -> impl DerefMut<Target = T> + '_ {
+        RefMut::map(self.guard.borrow_mut(), |item| item.get_mut())
+    }
 }
@@ -1,63 +1,507 @@
-use std::fmt::Display;
+use std::{fmt::Display, io};
 
-use crate::timeline::sim::instr::MsgInstr;
+use crate::timeline::{
+    sim::instr::{BcastInstr, McastInstr, MsgInstr, Payload},
+    snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter},
+};
+
+/// Size in bytes of an acknowledgement.
+const ACK_SIZE: u32 = 8;
+/// End node of packets that are flooded to every node.
+pub const BROADCAST: u32 = u32::MAX;
 
 pub enum Packet {
-    IcmpRequest(IcmpReplyPacket),
-    IcmpReply(IcmpReqPacket),
     Message(MessagePacket),
+    Ack(AckPacket),
+}
+
+impl Packet {
+    /// Tick at which the packet entered the network.
+    pub fn timestamp(&self) -> u32 {
+        match self {
+            Packet::Message(m) => m.timestamp,
+            Packet::Ack(a) => a.timestamp,
+        }
+    }
+
+    /// Moves the packet to `node`, which it reached at `tick`.
+    pub fn set_current_node(&mut self, node: u32, tick: u32) {
+        match self {
+            Packet::Message(m) => {
+                if m.current_node != node {
+                    m.prev_node = Some(m.current_node);
+                    m.path.push(Hop::new(node, tick));
+                }
+                m.current_node = node;
+            }
+            Packet::Ack(a) => a.current_node = node,
+        }
+    }
+
+    /// Node the packet is at, or was last sent from if it's on a link.
+    pub fn current_node(&self) -> u32 {
+        match self {
+            Packet::Message(m) => m.current_node,
+            Packet::Ack(a) => a.current_node,
+        }
+    }
+
+    pub fn end_node(&self) -> u32 {
+        match self {
+            Packet::Message(m) => m.end_node,
+            Packet::Ack(a) => a.end_node,
+        }
+    }
+
+    /// Number of bytes the packet occupies on a link or in a queue.
+    pub fn size(&self) -> u32 {
+        match self {
+            Packet::Message(m) => m.size,
+            Packet::Ack(_) => ACK_SIZE,
+        }
+    }
 }
 
 pub struct MessagePacket {
     timestamp: u32,
     start_node: u32,
     current_node: u32,
+    /// Node the packet was last forwarded from, if it has moved yet.
+    prev_node: Option<u32>,
     end_node: u32,
     id: u32,
-    // Data
+    /// Which message sent with this ID this is, counting from 1, as sims
+    /// may use an ID more than once.
+    nth: u32,
+    size: u32,
+    payload: Option<Vec<u8>>,
+    hops: u32,
+    /// Nodes visited so far, starting with the start node.
+    path: Vec<Hop>,
+    frag: Option<Fragment>,
+    /// Which send of a reliable message this is, counting from 1.
+    attempt: Option<u32>,
+}
+
+/// Marks a packet as carrying part of a larger message.
+#[derive(Debug, Clone, Copy)]
+pub struct Fragment {
+    /// Byte offset of this fragment within the original message.
+    offset: u32,
+    /// Size in bytes of the original message.
+    total: u32,
+}
+
+/// A node on a packet's path, with when the packet got there and left.
+#[derive(Debug, Clone, Copy)]
+pub struct Hop {
+    node: u32,
+    arrived: u32,
+    departed: Option<u32>,
+}
+
+impl Hop {
+    fn new(node: u32, arrived: u32) -> Hop {
+        Hop {
+            node,
+            arrived,
+            departed: None,
+        }
+    }
+    pub fn node(&self) -> u32 {
+        self.node
+    }
+}
+
+impl Fragment {
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+}
+
+impl MessagePacket {
+    fn new(timestamp: u32, id: u32, start_node: u32, end_node: u32, payload: &Payload) -> Self {
+        MessagePacket {
+            timestamp,
+            start_node,
+            current_node: start_node,
+            prev_node: None,
+            end_node,
+            id,
+            nth: 1,
+            size: payload.size(),
+            payload: payload.data().map(|p| p.to_vec()),
+            hops: 0,
+            path: vec![Hop::new(start_node, timestamp)],
+            frag: None,
+            attempt: None,
+        }
+    }
+
+    /// Builds one packet per destination of a multicast.
+    pub fn multicast(value: &McastInstr) -> Vec<MessagePacket> {
+        value
+            .end_nodes()
+            .iter()
+            .map(|&end_node| {
+                MessagePacket::new(
+                    value.timestamp(),
+                    value.msg_id(),
+                    value.start_node(),
+                    end_node,
+                    value.payload(),
+                )
+            })
+            .collect()
+    }
+
+    pub fn timestamp(&self) -> u32 {
+        self.timestamp
+    }
+    pub fn start_node(&self) -> u32 {
+        self.start_node
+    }
+    pub fn end_node(&self) -> u32 {
+        self.end_node
+    }
+    pub fn prev_node(&self) -> Option<u32> {
+        self.prev_node
+    }
+    pub fn is_broadcast(&self) -> bool {
+        self.end_node == BROADCAST
+    }
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+    pub fn nth(&self) -> u32 {
+        self.nth
+    }
+    pub fn set_nth(&mut self, nth: u32) {
+        self.nth = nth;
+    }
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+    pub fn hops(&self) -> u32 {
+        self.hops
+    }
+    pub fn add_hop(&mut self) {
+        self.hops += 1;
+    }
+    pub fn path(&self) -> &[Hop] {
+        &self.path
+    }
+    /// Records the packet leaving its current node at `tick`.
+    pub fn depart(&mut self, tick: u32) {
+        if let Some(hop) = self.path.last_mut() {
+            hop.departed = Some(tick);
+        }
+    }
+    pub fn frag(&self) -> Option<Fragment> {
+        self.frag
+    }
+    pub fn attempt(&self) -> Option<u32> {
+        self.attempt
+    }
+
+    /// Makes a fresh copy of the packet for resending, as it was when it
+    /// left its start node.
+    pub fn resend(&self, timestamp: u32, attempt: u32) -> MessagePacket {
+        MessagePacket {
+            timestamp,
+            current_node: self.start_node,
+            prev_node: None,
+            payload: self.payload.clone(),
+            hops: 0,
+            path: vec![Hop::new(self.start_node, timestamp)],
+            attempt: Some(attempt),
+            ..self.clone_header()
+        }
+    }
+
+    /// Splits the packet into fragments no larger than `mtu` bytes.
+    ///
+    /// Fragments of an existing fragment keep their offsets relative to the
+    /// original message, so they can all be reassembled together. Each
+    /// fragment carries the slice of the payload that falls within it.
+    pub fn fragment(self, mtu: u32) -> Vec<MessagePacket> {
+        let base = self.frag.map_or(0, |f| f.offset());
+        let total = self.frag.map_or(self.size, |f| f.total());
+
+        let mut frags = Vec::with_capacity(self.size.div_ceil(mtu) as usize);
+        let mut rel: u32 = 0;
+        while rel < self.size {
+            let len = mtu.min(self.size - rel);
+            let payload = self.payload.as_ref().and_then(|p| {
+                let start = rel as usize;
+                let end = ((rel + len) as usize).min(p.len());
+                (start < end).then(|| p[start..end].to_vec())
+            });
+            frags.push(MessagePacket {
+                size: len,
+                payload,
+                frag: Some(Fragment {
+                    offset: base + rel,
+                    total,
+                }),
+                ..self.clone_header()
+            });
+            rel += len;
+        }
+        frags
+    }
+
+    /// Turns the last fragment of a message to arrive back into the whole
+    /// message, carrying the reassembled `payload`.
+    pub fn reassembled(self, payload: Option<Vec<u8>>) -> MessagePacket {
+        MessagePacket {
+            size: self.frag.map_or(self.size, |f| f.total()),
+            payload,
+            frag: None,
+            ..self
+        }
+    }
+
+    fn clone_header(&self) -> MessagePacket {
+        MessagePacket {
+            timestamp: self.timestamp,
+            start_node: self.start_node,
+            current_node: self.current_node,
+            prev_node: self.prev_node,
+            end_node: self.end_node,
+            id: self.id,
+            nth: self.nth,
+            size: self.size,
+            payload: None,
+            hops: self.hops,
+            path: self.path.clone(),
+            frag: self.frag,
+            attempt: self.attempt,
+        }
+    }
+
+    /// Copies the packet for forwarding out of one more link, as broadcasts do.
+    pub fn duplicate(&self) -> MessagePacket {
+        MessagePacket {
+            payload: self.payload.clone(),
+            ..self.clone_header()
+        }
+    }
+
+    pub fn payload(&self) -> Option<&[u8]> {
+        self.payload.as_deref()
+    }
+    pub fn take_payload(&mut self) -> Option<Vec<u8>> {
+        self.payload.take()
+    }
 }
 
 impl From<&MsgInstr> for MessagePacket {
     fn from(value: &MsgInstr) -> Self {
         MessagePacket {
-            timestamp: value.timestamp(),
-            start_node: value.start_node(),
-            current_node: value.start_node(),
-            end_node: value.end_node(),
-            id: value.msg_id(),
+            attempt: value.reliability().map(|_| 1),
+            ..MessagePacket::new(
+                value.timestamp(),
+                value.msg_id(),
+                value.start_node(),
+                value.end_node(),
+                value.payload(),
+            )
         }
     }
 }
 
-pub struct IcmpReplyPacket {
-    timestamp: u32,
-    who_asked_node: u32,
-    current_node: u32,
-    queried_node: u32,
-    node_found: bool,
+impl From<&BcastInstr> for MessagePacket {
+    fn from(value: &BcastInstr) -> Self {
+        MessagePacket::new(
+            value.timestamp(),
+            value.msg_id(),
+            value.start_node(),
+            BROADCAST,
+            value.payload(),
+        )
+    }
 }
 
-pub struct IcmpReqPacket {
+/// Acknowledges that the end node of a reliable message received it.
+pub struct AckPacket {
     timestamp: u32,
-    who_asked_node: u32,
     current_node: u32,
-    queried_node: u32,
+    end_node: u32,
+    msg_id: u32,
+    nth: u32,
+    attempt: u32,
+}
+
+impl AckPacket {
+    /// Builds the ACK `msg`'s end node sends back to its start node.
+    pub fn new(timestamp: u32, msg: &MessagePacket) -> AckPacket {
+        AckPacket {
+            timestamp,
+            current_node: msg.end_node,
+            end_node: msg.start_node,
+            msg_id: msg.id,
+            nth: msg.nth,
+            attempt: msg.attempt.unwrap_or(1),
+        }
+    }
+    pub fn msg_id(&self) -> u32 {
+        self.msg_id
+    }
+    pub fn nth(&self) -> u32 {
+        self.nth
+    }
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
 }
 
+// Packets are ordered by the tick they entered the network
 impl PartialEq for Packet {
     fn eq(&self, other: &Self) -> bool {
-        todo!()
+        self.timestamp() == other.timestamp()
     }
 }
 
 impl PartialOrd for Packet {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        todo!()
+        self.timestamp().partial_cmp(&other.timestamp())
     }
 }
 
 impl Display for Packet {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        match self {
+            Packet::Message(m) => {
+                match m.is_broadcast() {
+                    true => write!(f, "bcast {} ({} -> all", m.id, m.start_node)?,
+                    false => write!(f, "msg {} ({} -> {}", m.id, m.start_node, m.end_node)?,
+                }
+                write!(f, ", {} bytes) at node {}", m.size, m.current_node)?;
+                match m.frag {
+                    Some(frag) => write!(f, ", fragment at offset {}/{}", frag.offset, frag.total),
+                    None => Ok(()),
+                }
+            }
+            Packet::Ack(a) => write!(
+                f,
+                "ack for msg {} (attempt {}) at node {}",
+                a.msg_id, a.attempt, a.current_node
+            ),
+        }
+    }
+}
+
+// Written as `node@arrived`, or `node@arrived..departed` if the packet
+// stayed for a while
+impl Display for Hop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.departed {
+            Some(departed) if departed != self.arrived => {
+                write!(f, "{}@{}..{}", self.node, self.arrived, departed)
+            }
+            _ => write!(f, "{}@{}", self.node, self.arrived),
+        }
+    }
+}
+
+impl Snap for Hop {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.val(self.node)?;
+        out.val(self.arrived)?;
+        out.opt(self.departed)
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        Ok(Hop {
+            node: inp.val()?,
+            arrived: inp.val()?,
+            departed: inp.opt()?,
+        })
+    }
+}
+
+impl Snap for MessagePacket {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.val(self.timestamp)?;
+        out.val(self.start_node)?;
+        out.val(self.current_node)?;
+        out.opt(self.prev_node)?;
+        out.val(self.end_node)?;
+        out.val(self.id)?;
+        out.val(self.nth)?;
+        out.val(self.size)?;
+        out.bytes(self.payload.as_deref())?;
+        out.val(self.hops)?;
+        out.opt(self.attempt)?;
+        out.opt(self.frag.map(|f| f.offset))?;
+        if let Some(frag) = self.frag {
+            out.val(frag.total)?;
+        }
+        out.list(self.path.iter())
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        let mut packet = MessagePacket {
+            timestamp: inp.val()?,
+            start_node: inp.val()?,
+            current_node: inp.val()?,
+            prev_node: inp.opt()?,
+            end_node: inp.val()?,
+            id: inp.val()?,
+            nth: inp.val()?,
+            size: inp.val()?,
+            payload: inp.bytes()?,
+            hops: inp.val()?,
+            path: Vec::new(),
+            frag: None,
+            attempt: inp.opt()?,
+        };
+        if let Some(offset) = inp.opt()? {
+            packet.frag = Some(Fragment {
+                offset,
+                total: inp.val()?,
+            });
+        }
+        packet.path = inp.list()?;
+        Ok(packet)
+    }
+}
+
+impl Snap for Packet {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        match self {
+            Packet::Message(m) => {
+                out.val("msg")?;
+                m.save(out)
+            }
+            Packet::Ack(a) => {
+                out.val("ack")?;
+                out.val(a.timestamp)?;
+                out.val(a.current_node)?;
+                out.val(a.end_node)?;
+                out.val(a.msg_id)?;
+                out.val(a.nth)?;
+                out.val(a.attempt)
+            }
+        }
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        match inp.word()?.as_str() {
+            "msg" => Ok(Packet::Message(MessagePacket::load(inp)?)),
+            "ack" => Ok(Packet::Ack(AckPacket {
+                timestamp: inp.val()?,
+                current_node: inp.val()?,
+                end_node: inp.val()?,
+                msg_id: inp.val()?,
+                nth: inp.val()?,
+                attempt: inp.val()?,
+            })),
+            _ => Err(SnapErrCode::BadToken),
+        }
     }
 }
@@ -0,0 +1,771 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    io::{self, Write},
+};
+
+use super::packet::{Hop, MessagePacket};
+use crate::timeline::snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter};
+
+/// Why a message was thrown away before reaching its destination.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DropReason {
+    QueueFull,
+    NoRoute,
+    UnknownNode,
+    /// Lost on a lossy link.
+    LinkLoss,
+}
+
+/// Where a message currently is, or how it left the network.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MsgStatus {
+    /// A broadcast hasn't arrived at this node yet.
+    NotReached,
+    Queued {
+        node: u32,
+    },
+    InTransit {
+        from: u32,
+        to: u32,
+    },
+    Reassembling {
+        node: u32,
+        received: u32,
+        total: u32,
+    },
+    Delivered {
+        tick: u32,
+    },
+    PartiallyDelivered {
+        tick: u32,
+        received: u32,
+        total: u32,
+    },
+    Dropped {
+        tick: u32,
+        node: u32,
+        reason: DropReason,
+    },
+}
+
+impl MsgStatus {
+    /// Whether the message has left the network for good.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            MsgStatus::Delivered { .. } | MsgStatus::PartiallyDelivered { .. }
+        )
+    }
+}
+
+/// How a reliable message's exchange with its end node turned out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AckOutcome {
+    Pending,
+    Acked { tick: u32 },
+    Failed { tick: u32 },
+}
+
+/// Retransmission history of a message sent in reliable mode.
+#[derive(Debug, Clone)]
+pub struct AckReport {
+    attempts: u32,
+    rtt_samples: Vec<u32>,
+    outcome: AckOutcome,
+}
+
+impl AckReport {
+    pub(crate) fn new() -> AckReport {
+        AckReport {
+            attempts: 1,
+            rtt_samples: Vec::new(),
+            outcome: AckOutcome::Pending,
+        }
+    }
+    /// Number of times the message was sent, counting the first.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+    /// Round-trip time of each ACK that made it back, in ticks.
+    pub fn rtt_samples(&self) -> &[u32] {
+        &self.rtt_samples
+    }
+    pub fn outcome(&self) -> AckOutcome {
+        self.outcome
+    }
+    pub fn set_attempts(&mut self, attempts: u32) {
+        self.attempts = attempts;
+    }
+    pub fn add_rtt_sample(&mut self, rtt: u32) {
+        self.rtt_samples.push(rtt);
+    }
+    pub fn set_outcome(&mut self, outcome: AckOutcome) {
+        self.outcome = outcome;
+    }
+}
+
+/// What happened to a message at one of its destinations.
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    status: MsgStatus,
+    hops: u32,
+    fragments: u32,
+    payload: Option<Vec<u8>>,
+    /// Nodes the message passed through to get here, or to where it was lost.
+    path: Vec<Hop>,
+}
+
+impl Delivery {
+    fn new(status: MsgStatus) -> Delivery {
+        Delivery {
+            status,
+            hops: 0,
+            fragments: 0,
+            payload: None,
+            path: Vec::new(),
+        }
+    }
+    pub fn status(&self) -> MsgStatus {
+        self.status
+    }
+    pub fn hops(&self) -> u32 {
+        self.hops
+    }
+    /// Number of fragments the message arrived in, 0 if it wasn't split.
+    pub fn fragments(&self) -> u32 {
+        self.fragments
+    }
+    pub fn payload(&self) -> Option<&[u8]> {
+        self.payload.as_deref()
+    }
+    pub fn path(&self) -> &[Hop] {
+        &self.path
+    }
+    /// Records where the message is now.
+    ///
+    /// Once a message is delivered its status no longer changes, and while
+    /// it is being reassembled only the reassembly itself may update it, so
+    /// stray fragments still moving through the network can't overwrite it.
+    /// A partial delivery can still be completed by a resent copy.
+    ///
+    /// Returns whether the status was taken, so the caller knows whether
+    /// the rest of the delivery details should follow it.
+    pub fn set_status(&mut self, status: MsgStatus) -> bool {
+        let reassembly_update = matches!(
+            status,
+            MsgStatus::Reassembling { .. }
+                | MsgStatus::Delivered { .. }
+                | MsgStatus::PartiallyDelivered { .. }
+        );
+        let accepted = match self.status {
+            MsgStatus::PartiallyDelivered { .. } => matches!(status, MsgStatus::Delivered { .. }),
+            s if s.is_final() => false,
+            MsgStatus::Reassembling { .. } => reassembly_update,
+            _ => true,
+        };
+        if accepted {
+            self.status = status;
+        }
+        accepted
+    }
+    pub fn set_hops(&mut self, hops: u32) {
+        self.hops = hops;
+    }
+    pub fn set_fragments(&mut self, fragments: u32) {
+        self.fragments = fragments;
+    }
+    pub fn set_payload(&mut self, payload: Option<Vec<u8>>) {
+        self.payload = payload;
+    }
+    pub fn set_path(&mut self, path: Vec<Hop>) {
+        self.path = path;
+    }
+    /// Writes the path of the message as `, via 4@3..5 -> 1@7`, if it has one.
+    fn fmt_path(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            return Ok(());
+        }
+        let hops: Vec<String> = self.path.iter().map(|hop| hop.to_string()).collect();
+        write!(f, ", via {}", hops.join(" -> "))
+    }
+}
+
+/// How a message was addressed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MsgKind {
+    Unicast,
+    Multicast,
+    Broadcast,
+}
+
+/// Everything `rep` knows about a single message.
+///
+/// Reports compare by message ID, then by which message sent with that
+/// ID they're about, so they can be kept in an `AvlTree` and looked up by
+/// ID.
+#[derive(Debug, Clone)]
+pub struct MsgReport {
+    id: u32,
+    /// Which message sent with this ID this is, counting from 1.
+    nth: u32,
+    kind: MsgKind,
+    start_node: u32,
+    size: u32,
+    injected_at: u32,
+    /// Delivery status at each destination, keyed by node ID.
+    deliveries: BTreeMap<u32, Delivery>,
+    ack: Option<AckReport>,
+}
+
+impl MsgReport {
+    pub fn new(packet: &MessagePacket) -> MsgReport {
+        MsgReport {
+            ack: packet.attempt().map(|_| AckReport::new()),
+            ..MsgReport::with_dests(
+                packet,
+                MsgKind::Unicast,
+                [packet.end_node()],
+                MsgStatus::Queued {
+                    node: packet.start_node(),
+                },
+            )
+        }
+    }
+
+    /// Report for a message sent to each of `end_nodes`.
+    pub fn multicast(packet: &MessagePacket, end_nodes: &[u32]) -> MsgReport {
+        let queued = MsgStatus::Queued {
+            node: packet.start_node(),
+        };
+        MsgReport::with_dests(
+            packet,
+            MsgKind::Multicast,
+            end_nodes.iter().copied(),
+            queued,
+        )
+    }
+
+    /// Report for a message flooded to every node in `nodes` but its start node.
+    pub fn broadcast<I>(packet: &MessagePacket, nodes: I) -> MsgReport
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        let start = packet.start_node();
+        let dests = nodes.into_iter().filter(|&node| node != start);
+        MsgReport::with_dests(packet, MsgKind::Broadcast, dests, MsgStatus::NotReached)
+    }
+
+    fn with_dests<I>(
+        packet: &MessagePacket,
+        kind: MsgKind,
+        dests: I,
+        status: MsgStatus,
+    ) -> MsgReport
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        MsgReport {
+            id: packet.id(),
+            nth: packet.nth(),
+            kind,
+            start_node: packet.start_node(),
+            size: packet.size(),
+            injected_at: packet.timestamp(),
+            deliveries: dests
+                .into_iter()
+                .map(|dest| (dest, Delivery::new(status)))
+                .collect(),
+            ack: None,
+        }
+    }
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+    /// 1 for the first message sent with its ID, 2 for the next one to
+    /// reuse the ID, and so on.
+    pub fn nth(&self) -> u32 {
+        self.nth
+    }
+    /// The message's ID and [`MsgReport::nth`], which together tell apart
+    /// every message of a run.
+    pub fn key(&self) -> (u32, u32) {
+        (self.id, self.nth)
+    }
+    pub fn kind(&self) -> MsgKind {
+        self.kind
+    }
+    pub fn start_node(&self) -> u32 {
+        self.start_node
+    }
+    pub fn injected_at(&self) -> u32 {
+        self.injected_at
+    }
+    /// Delivery at each destination, in order of node ID.
+    pub fn deliveries(&self) -> impl Iterator<Item = (u32, &Delivery)> {
+        self.deliveries
+            .iter()
+            .map(|(&dest, delivery)| (dest, delivery))
+    }
+    pub fn delivery_mut(&mut self, dest: u32) -> Option<&mut Delivery> {
+        self.deliveries.get_mut(&dest)
+    }
+    /// Retransmission history, if the message was sent in reliable mode.
+    pub fn ack(&self) -> Option<&AckReport> {
+        self.ack.as_ref()
+    }
+    pub fn ack_mut(&mut self) -> Option<&mut AckReport> {
+        self.ack.as_mut()
+    }
+
+    /// Writes the report as a single line of JSON, as of `tick`, e.g.
+    ///
+    /// ```text
+    /// {"tick":50,"id":1,"kind":"msg","src":4,"size":64,"injected":3,"deliveries":[
+    /// {"dst":1,"status":"delivered","at":7,"hops":1,"path":["4@3..4","1@7"]}]}
+    /// ```
+    ///
+    /// (shown wrapped here). Reliable messages also get an `ack` object, and
+    /// messages that reuse an earlier message's ID an `nth` field.
+    pub fn write_json<W: Write>(&self, out: &mut W, tick: u32) -> io::Result<()> {
+        let kind = match self.kind {
+            MsgKind::Unicast => "msg",
+            MsgKind::Multicast => "mcast",
+            MsgKind::Broadcast => "bcast",
+        };
+        write!(
+            out,
+            "{{\"tick\":{tick},\"id\":{},\"kind\":\"{kind}\",\"src\":{},\"size\":{},\"injected\":{}",
+            self.id, self.start_node, self.size, self.injected_at
+        )?;
+        if self.nth > 1 {
+            write!(out, ",\"nth\":{}", self.nth)?;
+        }
+        write!(out, ",\"deliveries\":[")?;
+        for (i, (dest, delivery)) in self.deliveries.iter().enumerate() {
+            if i > 0 {
+                write!(out, ",")?;
+            }
+            write!(out, "{{\"dst\":{dest},")?;
+            delivery.write_json(out)?;
+            write!(out, "}}")?;
+        }
+        write!(out, "]")?;
+        if let Some(ack) = &self.ack {
+            write!(out, ",\"ack\":")?;
+            ack.write_json(out)?;
+        }
+        writeln!(out, "}}")
+    }
+}
+
+impl Delivery {
+    /// Writes the fields of the delivery, without the enclosing braces.
+    fn write_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        match self.status {
+            MsgStatus::NotReached => write!(out, "\"status\":\"notReached\"")?,
+            MsgStatus::Queued { node } => write!(out, "\"status\":\"queued\",\"node\":{node}")?,
+            MsgStatus::InTransit { from, to } => write!(
+                out,
+                "\"status\":\"inTransit\",\"from\":{from},\"to\":{to}"
+            )?,
+            MsgStatus::Reassembling {
+                node,
+                received,
+                total,
+            } => write!(
+                out,
+                "\"status\":\"reassembling\",\"node\":{node},\"received\":{received},\"total\":{total}"
+            )?,
+            MsgStatus::Delivered { tick } => write!(
+                out,
+                "\"status\":\"delivered\",\"at\":{tick},\"hops\":{}",
+                self.hops
+            )?,
+            MsgStatus::PartiallyDelivered {
+                tick,
+                received,
+                total,
+            } => write!(
+                out,
+                "\"status\":\"partiallyDelivered\",\"at\":{tick},\"received\":{received},\"total\":{total}"
+            )?,
+            MsgStatus::Dropped { tick, node, reason } => write!(
+                out,
+                "\"status\":\"dropped\",\"at\":{tick},\"node\":{node},\"reason\":\"{reason}\""
+            )?,
+        }
+        let hops: Vec<String> = self.path.iter().map(|hop| format!("\"{hop}\"")).collect();
+        write!(out, ",\"path\":[{}]", hops.join(","))
+    }
+}
+
+impl AckReport {
+    fn write_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let samples: Vec<String> = self.rtt_samples.iter().map(|s| s.to_string()).collect();
+        write!(
+            out,
+            "{{\"attempts\":{},\"rtt\":[{}]",
+            self.attempts,
+            samples.join(",")
+        )?;
+        match self.outcome {
+            AckOutcome::Pending => write!(out, ",\"outcome\":\"pending\"}}"),
+            AckOutcome::Acked { tick } => write!(out, ",\"outcome\":\"acked\",\"at\":{tick}}}"),
+            AckOutcome::Failed { tick } => write!(out, ",\"outcome\":\"failed\",\"at\":{tick}}}"),
+        }
+    }
+}
+
+impl PartialEq for MsgReport {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl PartialOrd for MsgReport {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.key().partial_cmp(&other.key())
+    }
+}
+
+impl Display for DropReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DropReason::QueueFull => write!(f, "queue full"),
+            DropReason::NoRoute => write!(f, "no route"),
+            DropReason::UnknownNode => write!(f, "unknown node"),
+            DropReason::LinkLoss => write!(f, "lost on link"),
+        }
+    }
+}
+
+impl Display for AckReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unit = if self.attempts == 1 {
+            "attempt"
+        } else {
+            "attempts"
+        };
+        write!(f, "{} {unit}", self.attempts)?;
+        if !self.rtt_samples.is_empty() {
+            let samples: Vec<String> = self.rtt_samples.iter().map(|s| s.to_string()).collect();
+            write!(f, ", rtt [{}]", samples.join(", "))?;
+        }
+        match self.outcome {
+            AckOutcome::Pending => write!(f, ", awaiting ack"),
+            AckOutcome::Acked { tick } => write!(f, ", acked at t={tick}"),
+            AckOutcome::Failed { tick } => write!(f, ", gave up at t={tick}"),
+        }
+    }
+}
+
+impl Display for Delivery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.status {
+            MsgStatus::NotReached => return write!(f, "not reached"),
+            MsgStatus::Queued { node } => return write!(f, "queued at node {node}"),
+            MsgStatus::InTransit { from, to } => return write!(f, "in transit {from} -> {to}"),
+            MsgStatus::Reassembling {
+                node,
+                received,
+                total,
+            } => return write!(f, "reassembling at node {node} ({received}/{total} bytes)"),
+            MsgStatus::Dropped { tick, node, reason } => {
+                write!(f, "dropped at node {node}, t={tick} ({reason})")?;
+                return self.fmt_path(f);
+            }
+            MsgStatus::Delivered { tick } => {
+                let unit = if self.hops == 1 { "hop" } else { "hops" };
+                write!(f, "delivered at t={tick} after {} {unit}", self.hops)?;
+                if self.fragments > 0 {
+                    write!(f, " in {} fragments", self.fragments)?;
+                }
+            }
+            MsgStatus::PartiallyDelivered {
+                tick,
+                received,
+                total,
+            } => write!(
+                f,
+                "partially delivered at t={tick} ({received}/{total} bytes in {} fragments)",
+                self.fragments
+            )?,
+        }
+        if let Some(payload) = &self.payload {
+            write!(f, ", payload \"{}\"", String::from_utf8_lossy(payload))?;
+        }
+        self.fmt_path(f)
+    }
+}
+
+impl Display for MsgReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dests: Vec<String> = self.deliveries.keys().map(|d| d.to_string()).collect();
+        let kind = match self.kind {
+            MsgKind::Unicast => "msg",
+            MsgKind::Multicast => "mcast",
+            MsgKind::Broadcast => "bcast",
+        };
+        write!(f, "{kind} {}", self.id)?;
+        // Only messages reusing an ID need telling apart from the first
+        if self.nth > 1 {
+            write!(f, " (#{})", self.nth)?;
+        }
+        match self.kind {
+            MsgKind::Broadcast => write!(f, " [{} -> all]", self.start_node)?,
+            _ => write!(f, " [{} -> {}]", self.start_node, dests.join(", "))?,
+        }
+        write!(
+            f,
+            " {} bytes, injected at t={}:",
+            self.size, self.injected_at
+        )?;
+
+        match self.kind {
+            MsgKind::Unicast => {
+                // Unicasts have exactly one destination
+                if let Some(delivery) = self.deliveries.values().next() {
+                    write!(f, " {delivery}")?;
+                }
+            }
+            MsgKind::Multicast | MsgKind::Broadcast => {
+                let reached = self
+                    .deliveries
+                    .values()
+                    .filter(|d| d.status().is_final())
+                    .count();
+                write!(
+                    f,
+                    " reached {reached}/{} destinations",
+                    self.deliveries.len()
+                )?;
+                for (dest, delivery) in self.deliveries.iter() {
+                    write!(f, "\n    {dest}: {delivery}")?;
+                }
+            }
+        }
+
+        match &self.ack {
+            Some(ack) => write!(f, "; reliable: {ack}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Snap for DropReason {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.val(match self {
+            DropReason::QueueFull => "queueFull",
+            DropReason::NoRoute => "noRoute",
+            DropReason::UnknownNode => "unknownNode",
+            DropReason::LinkLoss => "linkLoss",
+        })
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        match inp.word()?.as_str() {
+            "queueFull" => Ok(DropReason::QueueFull),
+            "noRoute" => Ok(DropReason::NoRoute),
+            "unknownNode" => Ok(DropReason::UnknownNode),
+            "linkLoss" => Ok(DropReason::LinkLoss),
+            _ => Err(SnapErrCode::BadToken),
+        }
+    }
+}
+
+impl Snap for MsgStatus {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        match *self {
+            MsgStatus::NotReached => out.val("notReached"),
+            MsgStatus::Queued { node } => {
+                out.val("queued")?;
+                out.val(node)
+            }
+            MsgStatus::InTransit { from, to } => {
+                out.val("inTransit")?;
+                out.val(from)?;
+                out.val(to)
+            }
+            MsgStatus::Reassembling {
+                node,
+                received,
+                total,
+            } => {
+                out.val("reassembling")?;
+                out.val(node)?;
+                out.val(received)?;
+                out.val(total)
+            }
+            MsgStatus::Delivered { tick } => {
+                out.val("delivered")?;
+                out.val(tick)
+            }
+            MsgStatus::PartiallyDelivered {
+                tick,
+                received,
+                total,
+            } => {
+                out.val("partiallyDelivered")?;
+                out.val(tick)?;
+                out.val(received)?;
+                out.val(total)
+            }
+            MsgStatus::Dropped { tick, node, reason } => {
+                out.val("dropped")?;
+                out.val(tick)?;
+                out.val(node)?;
+                reason.save(out)
+            }
+        }
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        Ok(match inp.word()?.as_str() {
+            "notReached" => MsgStatus::NotReached,
+            "queued" => MsgStatus::Queued { node: inp.val()? },
+            "inTransit" => MsgStatus::InTransit {
+                from: inp.val()?,
+                to: inp.val()?,
+            },
+            "reassembling" => MsgStatus::Reassembling {
+                node: inp.val()?,
+                received: inp.val()?,
+                total: inp.val()?,
+            },
+            "delivered" => MsgStatus::Delivered { tick: inp.val()? },
+            "partiallyDelivered" => MsgStatus::PartiallyDelivered {
+                tick: inp.val()?,
+                received: inp.val()?,
+                total: inp.val()?,
+            },
+            "dropped" => MsgStatus::Dropped {
+                tick: inp.val()?,
+                node: inp.val()?,
+                reason: DropReason::load(inp)?,
+            },
+            _ => return Err(SnapErrCode::BadToken),
+        })
+    }
+}
+
+impl Snap for Delivery {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        self.status.save(out)?;
+        out.val(self.hops)?;
+        out.val(self.fragments)?;
+        out.bytes(self.payload.as_deref())?;
+        out.list(self.path.iter())
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        Ok(Delivery {
+            status: MsgStatus::load(inp)?,
+            hops: inp.val()?,
+            fragments: inp.val()?,
+            payload: inp.bytes()?,
+            path: inp.list()?,
+        })
+    }
+}
+
+impl Snap for AckReport {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.val(self.attempts)?;
+        out.list(self.rtt_samples.iter())?;
+        match self.outcome {
+            AckOutcome::Pending => out.val("pending"),
+            AckOutcome::Acked { tick } => {
+                out.val("acked")?;
+                out.val(tick)
+            }
+            AckOutcome::Failed { tick } => {
+                out.val("failed")?;
+                out.val(tick)
+            }
+        }
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        let attempts = inp.val()?;
+        let rtt_samples = inp.list()?;
+        let outcome = match inp.word()?.as_str() {
+            "pending" => AckOutcome::Pending,
+            "acked" => AckOutcome::Acked { tick: inp.val()? },
+            "failed" => AckOutcome::Failed { tick: inp.val()? },
+            _ => return Err(SnapErrCode::BadToken),
+        };
+        Ok(AckReport {
+            attempts,
+            rtt_samples,
+            outcome,
+        })
+    }
+}
+
+impl Snap for MsgReport {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.tag("report")?;
+        out.val(self.id)?;
+        out.val(self.nth)?;
+        out.val(match self.kind {
+            MsgKind::Unicast => "unicast",
+            MsgKind::Multicast => "multicast",
+            MsgKind::Broadcast => "broadcast",
+        })?;
+        out.val(self.start_node)?;
+        out.val(self.size)?;
+        out.val(self.injected_at)?;
+        out.val(self.deliveries.len())?;
+        for (dest, delivery) in self.deliveries.iter() {
+            out.tag("delivery")?;
+            out.val(dest)?;
+            delivery.save(out)?;
+        }
+        out.tag("ack")?;
+        match &self.ack {
+            Some(ack) => {
+                out.val("+")?;
+                ack.save(out)
+            }
+            None => out.val("-"),
+        }
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        inp.tag("report")?;
+        let (id, nth) = (inp.val()?, inp.val()?);
+        let kind = match inp.word()?.as_str() {
+            "unicast" => MsgKind::Unicast,
+            "multicast" => MsgKind::Multicast,
+            "broadcast" => MsgKind::Broadcast,
+            _ => return Err(SnapErrCode::BadToken),
+        };
+        let (start_node, size, injected_at) = (inp.val()?, inp.val()?, inp.val()?);
+
+        let count: usize = inp.val()?;
+        let mut deliveries = BTreeMap::new();
+        for _ in 0..count {
+            inp.tag("delivery")?;
+            let dest = inp.val()?;
+            deliveries.insert(dest, Delivery::load(inp)?);
+        }
+
+        inp.tag("ack")?;
+        let ack = match inp.word()?.as_str() {
+            "+" => Some(AckReport::load(inp)?),
+            "-" => None,
+            _ => return Err(SnapErrCode::BadToken),
+        };
+        Ok(MsgReport {
+            id,
+            nth,
+            kind,
+            start_node,
+            size,
+            injected_at,
+            deliveries,
+            ack,
+        })
+    }
+}
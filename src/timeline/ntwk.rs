@@ -1,36 +1,75 @@
 use self::{
     file_utils::{NtwkErrCode, NtwkParseState},
     node::NtwkNode,
+    route::RouteTable,
+};
+use crate::{
+    timeline::{
+        snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter},
+        utils,
+    },
+    ErrCode, Routing,
+};
+use std::{
+    cell::{Ref, RefCell},
+    collections::HashMap,
+    fmt::Display,
+    io::{self, BufRead},
+    rc::Rc,
+    str::FromStr,
 };
-use crate::timeline::utils;
-use std::{cell::RefCell, rc::Rc};
 
+pub(crate) mod builder;
+pub(crate) mod analysis;
+mod dot;
 mod file_utils;
-mod node;
+pub(crate) mod generate;
+pub(crate) mod link;
+pub(crate) mod node;
+mod route;
+mod writer;
 
 const DEFAULT_NUM_NODES: usize = 20;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Ntwk {
     node_list: Vec<Rc<RefCell<NtwkNode>>>, //node_graph: Graph<Node>
+    /// Position in `node_list` of each node ID, so lookups while building
+    /// routes don't scan the list.
+    index: HashMap<u32, usize>,
+    routes: RouteTable,
 }
 
 impl Ntwk {
-    pub fn from_file(filename: &String) -> Result<Ntwk, NtwkErrCode> {
+    /// Reads a network from a `.ntwk` file, with fewest-hop routes.
+    pub fn from_file(filename: &str) -> Result<Ntwk, ErrCode> {
+        let lines = match utils::internal_utils::read_lines(filename) {
+            Ok(lines) => lines,
+            Err(e) => {
+                return Err(ErrCode::NtwkFileParseFailed {
+                    file: filename.to_string(),
+                    reason: format!("{}: {e}", NtwkErrCode::BadFile),
+                })
+            }
+        };
+        Ntwk::parse(lines).map_err(|status| status.into_err(filename))
+    }
+
+    /// Reads a network in the `.ntwk` format from `reader`, with fewest-hop
+    /// routes.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Ntwk, ErrCode> {
+        Ntwk::parse(reader.lines())
+            .map_err(|status| status.into_err(utils::internal_utils::READER_NAME))
+    }
+
+    fn parse(lines: impl Iterator<Item = io::Result<String>>) -> Result<Ntwk, NtwkErrCode> {
         let mut status = NtwkErrCode::Okay;
         let mut state = NtwkParseState::NewNode;
 
         let mut ntwk = Ntwk {
             node_list: Vec::with_capacity(DEFAULT_NUM_NODES),
-        };
-
-        let lines = match utils::internal_utils::read_lines(filename) {
-            Ok(lines) => lines,
-            Err(e) => {
-                eprintln!("{e}");
-                status = NtwkErrCode::BadFile;
-                return Err(status);
-            }
+            index: HashMap::with_capacity(DEFAULT_NUM_NODES),
+            routes: RouteTable::default(),
         };
 
         for line in lines {
@@ -39,7 +78,7 @@ impl Ntwk {
             }
 
             let s = line.unwrap_or("".to_string());
-            let s = utils::internal_utils::strip_comment(s);
+            let s = utils::internal_utils::clean_line(&s);
             if s.is_empty() {
                 continue;
             };
@@ -56,8 +95,96 @@ impl Ntwk {
         }
 
         match status {
-            NtwkErrCode::Okay => Ok(ntwk),
+            NtwkErrCode::Okay => {
+                ntwk.routes = RouteTable::build(&ntwk, Routing::default());
+                Ok(ntwk)
+            }
             _ => Err(status),
         }
     }
+
+    /// Rebuilds every route with `routing`.
+    pub fn set_routing(&mut self, routing: Routing) {
+        self.routes = RouteTable::build(self, routing);
+    }
+
+    pub fn node(&self, id: u32) -> Option<Ref<'_, NtwkNode>> {
+        self.index.get(&id).map(|&idx| self.node_list[idx].borrow())
+    }
+
+    /// Adds `node` after the others. If its ID is taken, lookups by ID keep
+    /// finding the first node with it.
+    fn push_node(&mut self, node: NtwkNode) {
+        self.index.entry(node.id()).or_insert(self.node_list.len());
+        self.node_list.push(Rc::new(RefCell::new(node)));
+    }
+
+    pub fn node_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.node_list.iter().map(|node| node.borrow().id())
+    }
+
+    /// Returns the neighbour `from` should forward to in order to reach `to`,
+    /// or `None` if `to` can't be reached from `from`.
+    pub fn next_hop(&self, from: u32, to: u32) -> Option<u32> {
+        self.routes.next_hop(from, to)
+    }
+}
+
+impl FromStr for Ntwk {
+    type Err = ErrCode;
+
+    fn from_str(s: &str) -> Result<Ntwk, ErrCode> {
+        Ntwk::from_reader(s.as_bytes())
+    }
+}
+
+impl NtwkErrCode {
+    fn into_err(self, filename: &str) -> ErrCode {
+        ErrCode::NtwkFileParseFailed {
+            file: filename.to_string(),
+            reason: self.to_string(),
+        }
+    }
+}
+
+impl Display for NtwkErrCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            NtwkErrCode::Okay => "no error",
+            NtwkErrCode::HasZeroConnections => "node without connections",
+            NtwkErrCode::MismatchEndNodeToken => "node doesn't end with endNode",
+            NtwkErrCode::BadFile => "file can't be read",
+            NtwkErrCode::BadLine(line) => return write!(f, "malformed line: {line}"),
+            NtwkErrCode::BadLinkAttr(attr) => return write!(f, "bad link attribute: {attr}"),
+        };
+        write!(f, "{reason}")
+    }
+}
+
+// Routes are saved along with the nodes rather than rebuilt, so a restored
+// run keeps forwarding exactly as the original did
+impl Snap for Ntwk {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.tag("ntwk")?;
+        out.val(self.node_list.len())?;
+        for node in self.node_list.iter() {
+            node.borrow().save(out)?;
+        }
+        self.routes.save(out)
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        inp.tag("ntwk")?;
+        let nodes: Vec<NtwkNode> = inp.list()?;
+        let mut ntwk = Ntwk {
+            node_list: Vec::with_capacity(nodes.len()),
+            index: HashMap::with_capacity(nodes.len()),
+            routes: RouteTable::default(),
+        };
+        for node in nodes {
+            ntwk.push_node(node);
+        }
+        ntwk.routes = RouteTable::load(inp)?;
+        Ok(ntwk)
+    }
 }
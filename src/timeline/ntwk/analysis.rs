@@ -0,0 +1,351 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt::Display,
+    io::{self, Write},
+};
+
+use super::Ntwk;
+
+/// Shape of a network, from [`Ntwk::analyze`].
+///
+/// Path lengths count hops along links in the direction they go.
+/// Articulation points and bridges treat every link as going both ways.
+/// Links to nodes the network doesn't have are left out throughout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub nodes: usize,
+    /// One-way links.
+    pub links: usize,
+    /// Pairs of nodes with a link between them either way.
+    pub edges: usize,
+    /// Number of nodes with each number of links out, fewest links first.
+    pub degrees: Vec<(usize, usize)>,
+    /// Longest shortest path between two nodes, in hops, or `None` if no
+    /// node can reach another.
+    pub diameter: Option<u32>,
+    /// Mean shortest path over every pair of nodes that can reach each
+    /// other.
+    pub avg_path_len: Option<f64>,
+    /// Ordered pairs of nodes where the first can't reach the second.
+    pub unreachable_pairs: usize,
+    /// Nodes whose loss would split the network, in ascending order.
+    pub articulation_points: Vec<u32>,
+    /// Edges whose loss would split the network, as `(lower, higher)` node
+    /// pairs in ascending order.
+    pub bridges: Vec<(u32, u32)>,
+    /// Each node's share of the shortest paths between other nodes that
+    /// pass through it, busiest first.
+    pub betweenness: Vec<(u32, f64)>,
+}
+
+impl Ntwk {
+    /// Works out the shape of the network, e.g. to spot nodes that will
+    /// be bottlenecks before running traffic over it.
+    ///
+    /// ```
+    /// # use guwah::{Ntwk, QueueDelays, Topology};
+    /// let line = Ntwk::generate(Topology::Line { nodes: 4 }, QueueDelays::default(), 0)?;
+    /// let analysis = line.analyze();
+    /// assert_eq!(analysis.diameter, Some(3));
+    /// assert_eq!(analysis.articulation_points, vec![1, 2]);
+    /// assert_eq!(analysis.bridges, vec![(0, 1), (1, 2), (2, 3)]);
+    /// # Ok::<(), guwah::ErrCode>(())
+    /// ```
+    pub fn analyze(&self) -> Analysis {
+        let ids: Vec<u32> = self.node_ids().collect();
+        let idx_of: HashMap<u32, usize> =
+            ids.iter().enumerate().map(|(idx, &id)| (id, idx)).collect();
+        let n = ids.len();
+
+        // Outgoing links, and links either way
+        let mut out: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut both: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for (from, node) in self.node_list.iter().map(|node| node.borrow()).enumerate() {
+            for to in node
+                .links()
+                .iter()
+                .filter_map(|link| idx_of.get(&link.to()))
+            {
+                out[from].push(*to);
+                if from != *to {
+                    both[from].push(*to);
+                    both[*to].push(from);
+                }
+            }
+        }
+
+        let links = out.iter().map(Vec::len).sum();
+        let mut degrees: BTreeMap<usize, usize> = BTreeMap::new();
+        for links in &out {
+            *degrees.entry(links.len()).or_default() += 1;
+        }
+
+        // Links to the same neighbour twice count once from here on
+        for links in out.iter_mut().chain(both.iter_mut()) {
+            links.sort_unstable();
+            links.dedup();
+        }
+
+        let paths = shortest_paths(&out);
+        let (cut_nodes, cut_edges) = cuts(&both);
+        let mut articulation_points: Vec<u32> = cut_nodes.into_iter().map(|idx| ids[idx]).collect();
+        articulation_points.sort_unstable();
+        let mut bridges: Vec<(u32, u32)> = cut_edges
+            .into_iter()
+            .map(|(a, b)| (ids[a].min(ids[b]), ids[a].max(ids[b])))
+            .collect();
+        bridges.sort_unstable();
+
+        // Share of the (n - 1)(n - 2) ordered pairs of other nodes
+        let pairs = n.saturating_sub(1) * n.saturating_sub(2);
+        let scale = if pairs > 0 { 1.0 / pairs as f64 } else { 0.0 };
+        let mut betweenness: Vec<(u32, f64)> = ids
+            .iter()
+            .zip(&paths.through)
+            .map(|(&id, &through)| (id, through * scale))
+            .collect();
+        betweenness.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        Analysis {
+            nodes: n,
+            links,
+            edges: both.iter().map(Vec::len).sum::<usize>() / 2,
+            degrees: degrees.into_iter().collect(),
+            diameter: (paths.reachable > 0).then_some(paths.longest),
+            avg_path_len: (paths.reachable > 0)
+                .then(|| paths.total as f64 / paths.reachable as f64),
+            unreachable_pairs: n * n.saturating_sub(1) - paths.reachable,
+            articulation_points,
+            bridges,
+            betweenness,
+        }
+    }
+}
+
+struct Paths {
+    /// Ordered pairs of distinct nodes where the first reaches the second.
+    reachable: usize,
+    longest: u32,
+    /// Sum of the shortest path lengths between reachable pairs.
+    total: u64,
+    /// Shortest paths through each node, counting a pair with several
+    /// shortest paths fractionally.
+    through: Vec<f64>,
+}
+
+/// Runs a breadth-first search from every node, accumulating betweenness
+/// as it goes (Brandes' algorithm). Each node's neighbours in `out` must
+/// be listed once.
+fn shortest_paths(out: &[Vec<usize>]) -> Paths {
+    let n = out.len();
+    let mut paths = Paths {
+        reachable: 0,
+        longest: 0,
+        total: 0,
+        through: vec![0.0; n],
+    };
+
+    for src in 0..n {
+        let mut dist: Vec<Option<u32>> = vec![None; n];
+        // Number of shortest paths from `src` to each node
+        let mut count = vec![0.0; n];
+        let mut preds: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut visited = Vec::with_capacity(n);
+        let mut frontier = VecDeque::from([src]);
+        dist[src] = Some(0);
+        count[src] = 1.0;
+
+        while let Some(node) = frontier.pop_front() {
+            visited.push(node);
+            let next = dist[node].unwrap_or(0) + 1;
+            for &neighbour in &out[node] {
+                if dist[neighbour].is_none() {
+                    dist[neighbour] = Some(next);
+                    frontier.push_back(neighbour);
+                }
+                if dist[neighbour] == Some(next) {
+                    count[neighbour] += count[node];
+                    preds[neighbour].push(node);
+                }
+            }
+        }
+
+        for &d in dist.iter().flatten().filter(|&&d| d > 0) {
+            paths.reachable += 1;
+            paths.longest = paths.longest.max(d);
+            paths.total += d as u64;
+        }
+
+        // Farthest nodes first, so each node's dependents are done before it
+        let mut dependency = vec![0.0; n];
+        for &node in visited.iter().rev() {
+            for &pred in &preds[node] {
+                dependency[pred] += count[pred] / count[node] * (1.0 + dependency[node]);
+            }
+            if node != src {
+                paths.through[node] += dependency[node];
+            }
+        }
+    }
+    paths
+}
+
+/// Finds the articulation points and bridges of an undirected graph with
+/// a depth-first search (Tarjan's algorithm), without recursing so large
+/// networks can't overflow the stack.
+fn cuts(both: &[Vec<usize>]) -> (Vec<usize>, Vec<(usize, usize)>) {
+    let n = both.len();
+    let mut discovered: Vec<Option<usize>> = vec![None; n];
+    // Earliest discovered node reachable through each node's subtree
+    let mut low = vec![0; n];
+    let mut is_cut = vec![false; n];
+    let mut bridges = Vec::new();
+    let mut time = 0;
+
+    for root in 0..n {
+        if discovered[root].is_some() {
+            continue;
+        }
+        discovered[root] = Some(time);
+        low[root] = time;
+        time += 1;
+        let mut root_children = 0;
+        // (node, its parent, index of the next neighbour to visit)
+        let mut stack: Vec<(usize, Option<usize>, usize)> = vec![(root, None, 0)];
+
+        while let Some(&(node, parent, next)) = stack.last() {
+            if let Some(&neighbour) = both[node].get(next) {
+                stack.last_mut().unwrap().2 += 1;
+                match discovered[neighbour] {
+                    None => {
+                        discovered[neighbour] = Some(time);
+                        low[neighbour] = time;
+                        time += 1;
+                        if node == root {
+                            root_children += 1;
+                        }
+                        stack.push((neighbour, Some(node), 0));
+                    }
+                    Some(seen) if Some(neighbour) != parent => low[node] = low[node].min(seen),
+                    Some(_) => (),
+                }
+                continue;
+            }
+
+            stack.pop();
+            if let Some(parent) = parent {
+                low[parent] = low[parent].min(low[node]);
+                let parent_seen = discovered[parent].unwrap_or(0);
+                if low[node] > parent_seen {
+                    bridges.push((parent, node));
+                }
+                if parent != root && low[node] >= parent_seen {
+                    is_cut[parent] = true;
+                }
+            }
+        }
+        if root_children > 1 {
+            is_cut[root] = true;
+        }
+    }
+
+    let cut_nodes = (0..n).filter(|&node| is_cut[node]).collect();
+    (cut_nodes, bridges)
+}
+
+impl Analysis {
+    /// Writes the analysis as a single JSON object on one line.
+    pub fn write_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        write!(
+            out,
+            "{{\"nodes\":{},\"links\":{},\"edges\":{},\"degrees\":[",
+            self.nodes, self.links, self.edges
+        )?;
+        for (i, (degree, nodes)) in self.degrees.iter().enumerate() {
+            let sep = if i > 0 { "," } else { "" };
+            write!(out, "{sep}{{\"degree\":{degree},\"nodes\":{nodes}}}")?;
+        }
+        write!(out, "],\"diameter\":")?;
+        match self.diameter {
+            Some(diameter) => write!(out, "{diameter}")?,
+            None => write!(out, "null")?,
+        }
+        write!(out, ",\"avgPathLen\":")?;
+        match self.avg_path_len {
+            Some(avg) => write!(out, "{avg}")?,
+            None => write!(out, "null")?,
+        }
+        write!(
+            out,
+            ",\"unreachablePairs\":{},\"articulationPoints\":[",
+            self.unreachable_pairs
+        )?;
+        for (i, node) in self.articulation_points.iter().enumerate() {
+            write!(out, "{}{node}", if i > 0 { "," } else { "" })?;
+        }
+        write!(out, "],\"bridges\":[")?;
+        for (i, (a, b)) in self.bridges.iter().enumerate() {
+            write!(out, "{}[{a},{b}]", if i > 0 { "," } else { "" })?;
+        }
+        write!(out, "],\"betweenness\":[")?;
+        for (i, (node, share)) in self.betweenness.iter().enumerate() {
+            let sep = if i > 0 { "," } else { "" };
+            write!(out, "{sep}{{\"node\":{node},\"share\":{share}}}")?;
+        }
+        writeln!(out, "]}}")
+    }
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "nodes: {}", self.nodes)?;
+        writeln!(
+            f,
+            "links: {} one-way, between {} pairs of nodes",
+            self.links, self.edges
+        )?;
+        let degrees: Vec<String> = self
+            .degrees
+            .iter()
+            .map(|(degree, nodes)| match nodes {
+                1 => format!("{degree} (1 node)"),
+                _ => format!("{degree} ({nodes} nodes)"),
+            })
+            .collect();
+        writeln!(f, "links out per node: {}", degrees.join(", "))?;
+        match (self.diameter, self.avg_path_len) {
+            (Some(diameter), Some(avg)) => {
+                writeln!(f, "diameter: {diameter} hops")?;
+                writeln!(f, "average shortest path: {avg:.2} hops")?;
+            }
+            _ => writeln!(f, "diameter: no node reaches another")?,
+        }
+        if self.unreachable_pairs > 0 {
+            writeln!(f, "unreachable pairs: {}", self.unreachable_pairs)?;
+        }
+        let points: Vec<String> = self
+            .articulation_points
+            .iter()
+            .map(u32::to_string)
+            .collect();
+        writeln!(f, "articulation points: {}", list_or_none(&points))?;
+        let bridges: Vec<String> = self
+            .bridges
+            .iter()
+            .map(|(a, b)| format!("{a}-{b}"))
+            .collect();
+        writeln!(f, "bridges: {}", list_or_none(&bridges))?;
+        write!(f, "betweenness:")?;
+        for (node, share) in &self.betweenness {
+            write!(f, "\n  node {node}: {share:.3}")?;
+        }
+        Ok(())
+    }
+}
+
+fn list_or_none(items: &[String]) -> String {
+    match items.is_empty() {
+        true => "none".to_string(),
+        false => items.join(", "),
+    }
+}
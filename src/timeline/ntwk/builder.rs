@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use super::{file_utils::NtwkErrCode, link::NtwkLink, node::NtwkNode, route::RouteTable, Ntwk};
+use crate::{ErrCode, Routing};
+
+struct NodeSpec {
+    id: u32,
+    queue_delay: u32,
+    queue_capacity: Option<u32>,
+    links: Vec<NtwkLink>,
+}
+
+/// Builds a [`Ntwk`] in code rather than reading it from a `.ntwk` file.
+///
+/// Methods chain, and the first mistake is kept and returned by
+/// [`build`](NtwkBuilder::build):
+///
+/// ```
+/// # use guwah::NtwkBuilder;
+/// let ntwk = NtwkBuilder::new()
+///     .node(1, 1)
+///     .link(1, 2)
+///     .attr("bw", "100")
+///     .node(2, 1)
+///     .link(2, 1)
+///     .build()?;
+/// assert_eq!(ntwk.next_hop(1, 2), Some(2));
+/// # Ok::<(), guwah::ErrCode>(())
+/// ```
+#[derive(Default)]
+pub struct NtwkBuilder {
+    /// Nodes in the order they were added, as a file would list them.
+    nodes: Vec<NodeSpec>,
+    /// Index in `nodes` of each node ID.
+    index: HashMap<u32, usize>,
+    /// Index in `nodes` of the node whose last link was added most recently.
+    last_link: Option<usize>,
+    error: Option<String>,
+}
+
+impl NtwkBuilder {
+    pub fn new() -> NtwkBuilder {
+        NtwkBuilder::default()
+    }
+
+    /// Adds node `id`, which holds each packet for `queue_delay` ticks.
+    pub fn node(&mut self, id: u32, queue_delay: u32) -> &mut NtwkBuilder {
+        if self.spec(id).is_some() {
+            self.fail(format!("node {id} added twice"));
+        } else {
+            self.index.insert(id, self.nodes.len());
+            self.nodes.push(NodeSpec {
+                id,
+                queue_delay,
+                queue_capacity: None,
+                links: Vec::new(),
+            });
+        }
+        self
+    }
+
+    /// Bounds the number of bytes node `id` can hold at once.
+    pub fn queue_capacity(&mut self, id: u32, capacity: u32) -> &mut NtwkBuilder {
+        match self.spec(id) {
+            Some(node) => node.queue_capacity = Some(capacity),
+            None => self.fail(format!("queue capacity for unknown node {id}")),
+        }
+        self
+    }
+
+    /// Adds a one-way link from node `from`, which must already be added,
+    /// to node `to`.
+    pub fn link(&mut self, from: u32, to: u32) -> &mut NtwkBuilder {
+        match self.index.get(&from).copied() {
+            Some(idx) => {
+                self.nodes[idx].links.push(NtwkLink::new(to));
+                self.last_link = Some(idx);
+            }
+            None => self.fail(format!("link from unknown node {from}")),
+        }
+        self
+    }
+
+    /// Sets a `key=value` attribute, as a connection line takes them, on
+    /// the link added last.
+    pub fn attr(&mut self, key: &str, value: &str) -> &mut NtwkBuilder {
+        let link = self
+            .last_link
+            .and_then(|idx| self.nodes[idx].links.last_mut());
+        let result = match link {
+            Some(link) => link.set_attr(key, value).map_err(|e| e.to_string()),
+            None => Err(format!("link attribute {key}={value}: no link yet")),
+        };
+        if let Err(reason) = result {
+            self.fail(reason);
+        }
+        self
+    }
+
+    /// Puts the network together, with fewest-hop routes.
+    pub fn build(&self) -> Result<Ntwk, ErrCode> {
+        if let Some(reason) = &self.error {
+            return Err(ErrCode::BadScenario(reason.clone()));
+        }
+
+        let mut ntwk = Ntwk {
+            node_list: Vec::with_capacity(self.nodes.len()),
+            index: HashMap::with_capacity(self.nodes.len()),
+            routes: RouteTable::default(),
+        };
+        for spec in &self.nodes {
+            let mut node = NtwkNode::new(spec.id, spec.links.len() as u32, spec.queue_delay)
+                .map_err(|e: NtwkErrCode| ErrCode::BadScenario(format!("node {}: {e}", spec.id)))?;
+            if let Some(capacity) = spec.queue_capacity {
+                node.set_queue_capacity(capacity);
+            }
+            for link in &spec.links {
+                node.push(*link);
+            }
+            ntwk.push_node(node);
+        }
+        ntwk.routes = RouteTable::build(&ntwk, Routing::default());
+        Ok(ntwk)
+    }
+
+    fn spec(&mut self, id: u32) -> Option<&mut NodeSpec> {
+        let idx = *self.index.get(&id)?;
+        Some(&mut self.nodes[idx])
+    }
+
+    fn fail(&mut self, reason: String) {
+        self.error.get_or_insert(reason);
+    }
+}
@@ -0,0 +1,74 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use super::Ntwk;
+use crate::ErrCode;
+
+impl Ntwk {
+    /// Writes the network as a Graphviz `digraph` to the file `filename`,
+    /// highlighting the links in `highlight` as `write_dot` does.
+    pub fn write_dot_file(
+        &self,
+        filename: &str,
+        highlight: &HashSet<(u32, u32)>,
+    ) -> Result<(), ErrCode> {
+        let write = || -> io::Result<()> {
+            let mut out = BufWriter::new(File::create(filename)?);
+            self.write_dot(&mut out, highlight)?;
+            out.flush()
+        };
+        write().map_err(|source| ErrCode::DotFileFailed {
+            file: filename.to_string(),
+            source,
+        })
+    }
+
+    /// Writes the network as a Graphviz `digraph`.
+    ///
+    /// Nodes are labelled with their queue delay and capacity, and links
+    /// with any attributes set on them. Links in `highlight`, given as
+    /// `(from, to)` pairs, are drawn in red, e.g. to show the path a
+    /// message took.
+    pub fn write_dot<W: Write>(
+        &self,
+        out: &mut W,
+        highlight: &HashSet<(u32, u32)>,
+    ) -> io::Result<()> {
+        writeln!(out, "digraph ntwk {{")?;
+        writeln!(out, "    node [shape=circle];")?;
+        for node in self.node_list.iter().map(|node| node.borrow()) {
+            let mut label = format!("{}\\ndelay {}", node.id(), node.queue_delay());
+            if let Some(capacity) = node.queue_capacity() {
+                label.push_str(&format!("\\ncap {capacity}"));
+            }
+            writeln!(out, "    {} [label=\"{label}\"];", node.id())?;
+        }
+        for node in self.node_list.iter().map(|node| node.borrow()) {
+            for link in node.links() {
+                let mut attrs = Vec::new();
+                let label = link.attrs().join("\\n");
+                if !label.is_empty() {
+                    attrs.push(format!("label=\"{label}\""));
+                }
+                if highlight.contains(&(node.id(), link.to())) {
+                    attrs.push("color=red".to_string());
+                    attrs.push("penwidth=2".to_string());
+                }
+                match attrs.is_empty() {
+                    true => writeln!(out, "    {} -> {};", node.id(), link.to())?,
+                    false => writeln!(
+                        out,
+                        "    {} -> {} [{}];",
+                        node.id(),
+                        link.to(),
+                        attrs.join(", ")
+                    )?,
+                }
+            }
+        }
+        writeln!(out, "}}")
+    }
+}
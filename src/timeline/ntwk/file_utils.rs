@@ -1,6 +1,6 @@
-use std::{cell::RefCell, rc::Rc};
 use scanf::sscanf;
-use super::{node::NtwkNode, Ntwk};
+use super::{link::NtwkLink, node::NtwkNode, Ntwk};
+use crate::timeline::utils::internal_utils::split_attr;
 
 #[derive(PartialEq)]
 pub enum NtwkErrCode {
@@ -8,7 +8,10 @@ pub enum NtwkErrCode {
     HasZeroConnections,
     MismatchEndNodeToken,
     BadFile,
-    BadLine,
+    /// The line, with why it couldn't be read.
+    BadLine(String),
+    /// The `key=value` attribute that couldn't be applied.
+    BadLinkAttr(String),
 }
 
 pub enum NtwkParseState {
@@ -40,19 +43,28 @@ impl NtwkParseState {
         }
     }
 
-    pub fn parse_conn_list(ntwk: &mut Ntwk, str: &String) -> (NtwkErrCode, NtwkParseState) {
+    pub fn parse_conn_list(ntwk: &mut Ntwk, str: &str) -> (NtwkErrCode, NtwkParseState) {
         let mut next_state = NtwkParseState::GetConnections;
         let mut err = NtwkErrCode::Okay;
 
         let node_ref = ntwk.node_list.last().unwrap();
 
+        // Connection lines are `connectNodeID` optionally followed by
+        // `key=value` link attributes, e.g. `4,bw=16`
+        let mut tokens = str.split(',');
         let mut temp_id: u32 = 0;
-        let result = sscanf!(str, "{}", temp_id);
+        let result = sscanf!(tokens.next().unwrap_or(""), "{}", temp_id);
         if let Err(e) = result {
-            eprintln!("{e}");
-            err = NtwkErrCode::BadLine;
+            err = NtwkErrCode::BadLine(format!("{str} ({e})"));
         } else {
-            node_ref.borrow_mut().push(temp_id);
+            let mut link = NtwkLink::new(temp_id);
+            for tok in tokens {
+                let (key, value) = split_attr(tok);
+                if let Err(e) = link.set_attr(key, value) {
+                    return (e, next_state);
+                }
+            }
+            node_ref.borrow_mut().push(link);
             if node_ref.borrow().conn_len() >= node_ref.borrow().conn_count() {
                 next_state = NtwkParseState::EndNode;
             }
@@ -60,29 +72,38 @@ impl NtwkParseState {
         (err, next_state)
     }
 
-    pub fn parse_new_node(ntwk: &mut Ntwk, str: &String) -> (NtwkErrCode, NtwkParseState) {
+    pub fn parse_new_node(ntwk: &mut Ntwk, str: &str) -> (NtwkErrCode, NtwkParseState) {
         use NtwkParseState::GetConnections as get_conns; // Because I'm lazy and didn't want to retype 30 chars
         let mut err = NtwkErrCode::Okay;
         
         let mut temp_id: u32 = 0;
         let mut temp_conn_count: u32 = 0;
         let mut temp_queue_delay: u32 = 0;
+        let mut temp_queue_capacity: u32 = 0;
 
-        let result = sscanf!(str, "{},{},{}", temp_id, temp_conn_count, temp_queue_delay);
+        // The queue capacity is an optional fourth field
+        let has_capacity = str.matches(',').count() == 3;
+        let result = if has_capacity {
+            sscanf!(str, "{},{},{},{}", temp_id, temp_conn_count, temp_queue_delay, temp_queue_capacity)
+        } else {
+            sscanf!(str, "{},{},{}", temp_id, temp_conn_count, temp_queue_delay)
+        };
         if let Err(e) = result {
-            eprintln!("{e}");
-            err = NtwkErrCode::BadLine;
+            err = NtwkErrCode::BadLine(format!("{str} ({e})"));
         } else {
             let node = match NtwkNode::new(temp_id, temp_conn_count, temp_queue_delay) {
-                Ok(node) => node,
+                Ok(mut node) => {
+                    if has_capacity {
+                        node.set_queue_capacity(temp_queue_capacity);
+                    }
+                    node
+                }
                 Err(e) => {
                     err = e;
                     return (err, get_conns);
                 },
             };
-            let cell = RefCell::new(node);
-            let rc = Rc::new(cell);
-            ntwk.node_list.push(rc);
+            ntwk.push_node(node);
         }
         (err, get_conns)
     }
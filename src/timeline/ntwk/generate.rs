@@ -0,0 +1,366 @@
+use std::{fmt::Display, str::FromStr};
+
+use super::{builder::NtwkBuilder, Ntwk};
+use crate::{
+    timeline::utils::rng::{Rng, Stream},
+    ErrCode,
+};
+
+/// Most nodes a generated network can have. Every node keeps a route to
+/// every other, so the route table grows with the square of this.
+const MAX_NODES: u32 = 2000;
+
+/// A family of networks [`Ntwk::generate`] can build.
+///
+/// Nodes are numbered from 0, and every link is a pair of one-way links,
+/// one each way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Topology {
+    /// Nodes in a row.
+    Line { nodes: u32 },
+    /// A line whose ends are linked too.
+    Ring { nodes: u32 },
+    /// Node 0 linked to every other node.
+    Star { nodes: u32 },
+    /// Each node linked to the ones beside, above and below it.
+    Grid { rows: u32, cols: u32 },
+    /// A grid whose edges wrap around.
+    Torus { rows: u32, cols: u32 },
+    /// Every node linked to every other node.
+    Mesh { nodes: u32 },
+    /// Each node after 0 linked to its parent, with up to `fanout`
+    /// children per node, filled level by level.
+    Tree { nodes: u32, fanout: u32 },
+    /// Each pair of nodes linked with probability `p`.
+    ErdosRenyi { nodes: u32, p: f64 },
+    /// Grown from a mesh of `m + 1` nodes, each later node linked to `m`
+    /// others picked in proportion to how many links they already have.
+    BarabasiAlbert { nodes: u32, m: u32 },
+    /// Nodes scattered over the unit square, each pair linked with
+    /// probability `beta * exp(-d / (alpha * L))`, where `d` is their
+    /// distance and `L` the largest distance between any two nodes.
+    Waxman { nodes: u32, alpha: f64, beta: f64 },
+}
+
+/// How [`Ntwk::generate`] picks the queue delay of each node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueueDelays {
+    Constant(u32),
+    /// Whole number of ticks drawn uniformly from `min..=max`.
+    Uniform {
+        min: u32,
+        max: u32,
+    },
+}
+
+impl Default for QueueDelays {
+    fn default() -> Self {
+        QueueDelays::Constant(1)
+    }
+}
+
+impl Ntwk {
+    /// Builds a network of the given `topology`, with fewest-hop routes.
+    ///
+    /// Random topologies and delays are drawn from `seed`, so the same
+    /// arguments always give the same network. Since every node needs a
+    /// link, a node a random topology leaves without one is linked to
+    /// another node picked at random.
+    ///
+    /// ```
+    /// # use guwah::{Ntwk, QueueDelays, Topology};
+    /// let ring = Ntwk::generate(Topology::Ring { nodes: 6 }, QueueDelays::default(), 0)?;
+    /// assert_eq!(ring.next_hop(0, 2), Some(1));
+    /// assert_eq!(ring.next_hop(0, 4), Some(5));
+    /// # Ok::<(), guwah::ErrCode>(())
+    /// ```
+    pub fn generate(topology: Topology, delays: QueueDelays, seed: u64) -> Result<Ntwk, ErrCode> {
+        topology.check()?;
+        if let QueueDelays::Uniform { min, max } = delays {
+            if min > max {
+                return Err(ErrCode::BadScenario(format!(
+                    "queue delays {min}..{max} are empty"
+                )));
+            }
+        }
+
+        let nodes = topology.node_count();
+        let edges = topology.edges(&mut Rng::stream(seed, Stream::Topology));
+
+        let mut builder = NtwkBuilder::new();
+        let mut rng = Rng::stream(seed, Stream::QueueDelays);
+        for id in 0..nodes {
+            let delay = match delays {
+                QueueDelays::Constant(delay) => delay,
+                QueueDelays::Uniform { min, max } => min + rng.next_below_or_eq(max - min),
+            };
+            builder.node(id, delay);
+        }
+        for (a, b) in edges {
+            builder.link(a, b).link(b, a);
+        }
+        builder.build()
+    }
+}
+
+impl Topology {
+    fn node_count(&self) -> u32 {
+        match *self {
+            Topology::Grid { rows, cols } | Topology::Torus { rows, cols } => rows * cols,
+            Topology::Line { nodes }
+            | Topology::Ring { nodes }
+            | Topology::Star { nodes }
+            | Topology::Mesh { nodes }
+            | Topology::Tree { nodes, .. }
+            | Topology::ErdosRenyi { nodes, .. }
+            | Topology::BarabasiAlbert { nodes, .. }
+            | Topology::Waxman { nodes, .. } => nodes,
+        }
+    }
+
+    /// Rejects parameters that can't give a network where every node has
+    /// a link and no pair of nodes is linked twice.
+    fn check(&self) -> Result<(), ErrCode> {
+        let problem = match *self {
+            Topology::Ring { nodes } if nodes < 3 => Some("a ring needs at least 3 nodes"),
+            Topology::Grid { rows, cols } if rows.checked_mul(cols).is_none_or(|n| n < 2) => {
+                Some("a grid needs at least 2 nodes")
+            }
+            Topology::Torus { rows, cols }
+                if rows < 3 || cols < 3 || rows.checked_mul(cols).is_none() =>
+            {
+                Some("a torus needs at least 3 rows and 3 columns")
+            }
+            Topology::Tree { fanout: 0, .. } => Some("a tree needs a fanout of at least 1"),
+            Topology::ErdosRenyi { p, .. } if !(0.0..=1.0).contains(&p) => {
+                Some("the link probability must be between 0 and 1")
+            }
+            Topology::BarabasiAlbert { nodes, m } if m == 0 || m >= nodes => {
+                Some("each new node needs between 1 and nodes - 1 links")
+            }
+            Topology::Waxman { alpha, beta, .. } if !(alpha > 0.0 && beta > 0.0 && beta <= 1.0) => {
+                Some("alpha must be above 0 and beta between 0 and 1")
+            }
+            _ if self.node_count() < 2 => Some("a network needs at least 2 nodes"),
+            _ => None,
+        };
+        match problem {
+            Some(problem) => Err(ErrCode::BadScenario(format!("{self}: {problem}"))),
+            None if self.node_count() > MAX_NODES => Err(ErrCode::BadScenario(format!(
+                "{self}: a network can have at most {MAX_NODES} nodes"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Pairs of nodes to link, each pair once, in the order their links
+    /// are added.
+    fn edges(&self, rng: &mut Rng) -> Vec<(u32, u32)> {
+        let mut edges = Vec::new();
+        match *self {
+            Topology::Line { nodes } => edges.extend((1..nodes).map(|id| (id - 1, id))),
+            Topology::Ring { nodes } => {
+                edges.extend((1..nodes).map(|id| (id - 1, id)));
+                edges.push((nodes - 1, 0));
+            }
+            Topology::Star { nodes } => edges.extend((1..nodes).map(|id| (0, id))),
+            Topology::Grid { rows, cols } | Topology::Torus { rows, cols } => {
+                let wrap = matches!(self, Topology::Torus { .. });
+                let id = |row: u32, col: u32| row * cols + col;
+                for row in 0..rows {
+                    for col in 0..cols {
+                        if col + 1 < cols || wrap {
+                            edges.push((id(row, col), id(row, (col + 1) % cols)));
+                        }
+                        if row + 1 < rows || wrap {
+                            edges.push((id(row, col), id((row + 1) % rows, col)));
+                        }
+                    }
+                }
+            }
+            Topology::Mesh { nodes } => edges = all_pairs(0..nodes),
+            Topology::Tree { nodes, fanout } => {
+                edges.extend((1..nodes).map(|id| ((id - 1) / fanout, id)))
+            }
+            Topology::ErdosRenyi { nodes, p } => {
+                edges = all_pairs(0..nodes);
+                edges.retain(|_| rng.next_f64() < p);
+                link_loners(&mut edges, nodes, rng);
+            }
+            Topology::BarabasiAlbert { nodes, m } => {
+                edges = all_pairs(0..=m);
+                // One entry per link end, so a uniform pick from it favours
+                // nodes by how many links they have
+                let mut ends: Vec<u32> = edges.iter().flat_map(|&(a, b)| [a, b]).collect();
+                for id in m + 1..nodes {
+                    let mut targets: Vec<u32> = Vec::with_capacity(m as usize);
+                    while targets.len() < m as usize {
+                        let pick = ends[rng.next_below_or_eq(ends.len() as u32 - 1) as usize];
+                        if !targets.contains(&pick) {
+                            targets.push(pick);
+                        }
+                    }
+                    for target in targets {
+                        edges.push((target, id));
+                        ends.extend([target, id]);
+                    }
+                }
+            }
+            Topology::Waxman { nodes, alpha, beta } => {
+                let spots: Vec<(f64, f64)> = (0..nodes)
+                    .map(|_| (rng.next_f64(), rng.next_f64()))
+                    .collect();
+                let dist = |(a, b): (u32, u32)| {
+                    let ((ax, ay), (bx, by)) = (spots[a as usize], spots[b as usize]);
+                    (ax - bx).hypot(ay - by)
+                };
+                edges = all_pairs(0..nodes);
+                let longest = edges.iter().map(|&edge| dist(edge)).fold(0.0, f64::max);
+                edges.retain(|&edge| {
+                    let p = match longest > 0.0 {
+                        true => beta * (-dist(edge) / (alpha * longest)).exp(),
+                        false => beta,
+                    };
+                    rng.next_f64() < p
+                });
+                link_loners(&mut edges, nodes, rng);
+            }
+        }
+        edges
+    }
+}
+
+fn all_pairs(ids: impl Iterator<Item = u32> + Clone) -> Vec<(u32, u32)> {
+    let mut pairs = Vec::new();
+    for (idx, a) in ids.clone().enumerate() {
+        pairs.extend(ids.clone().skip(idx + 1).map(|b| (a, b)));
+    }
+    pairs
+}
+
+/// Links every node without a link to another node picked at random.
+fn link_loners(edges: &mut Vec<(u32, u32)>, nodes: u32, rng: &mut Rng) {
+    for id in 0..nodes {
+        if edges.iter().any(|&(a, b)| a == id || b == id) {
+            continue;
+        }
+        // Any node but `id` itself
+        let other = rng.next_below_or_eq(nodes - 2);
+        let other = if other >= id { other + 1 } else { other };
+        edges.push((id.min(other), id.max(other)));
+    }
+}
+
+impl Display for Topology {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Topology::Line { nodes } => write!(f, "line:{nodes}"),
+            Topology::Ring { nodes } => write!(f, "ring:{nodes}"),
+            Topology::Star { nodes } => write!(f, "star:{nodes}"),
+            Topology::Grid { rows, cols } => write!(f, "grid:{rows}x{cols}"),
+            Topology::Torus { rows, cols } => write!(f, "torus:{rows}x{cols}"),
+            Topology::Mesh { nodes } => write!(f, "mesh:{nodes}"),
+            Topology::Tree { nodes, fanout } => write!(f, "tree:{nodes}:{fanout}"),
+            Topology::ErdosRenyi { nodes, p } => write!(f, "er:{nodes}:{p}"),
+            Topology::BarabasiAlbert { nodes, m } => write!(f, "ba:{nodes}:{m}"),
+            Topology::Waxman { nodes, alpha, beta } => write!(f, "waxman:{nodes}:{alpha}:{beta}"),
+        }
+    }
+}
+
+impl FromStr for Topology {
+    type Err = ErrCode;
+
+    /// Parses the form [`Display`] writes, e.g. `ring:10`, `grid:4x5` or
+    /// `er:50:0.1`.
+    fn from_str(s: &str) -> Result<Topology, ErrCode> {
+        let bad = || ErrCode::BadScenario(format!("unknown topology {s}"));
+        let (kind, params) = s.split_once(':').ok_or_else(bad)?;
+        let params: Vec<&str> = params.split(':').collect();
+        let int = |idx: usize| {
+            params
+                .get(idx)
+                .and_then(|tok| tok.parse::<u32>().ok())
+                .ok_or_else(bad)
+        };
+        let float = |idx: usize| {
+            params
+                .get(idx)
+                .and_then(|tok| tok.parse::<f64>().ok())
+                .ok_or_else(bad)
+        };
+        let size = || {
+            let (rows, cols) = params[0].split_once('x').ok_or_else(bad)?;
+            match (rows.parse(), cols.parse()) {
+                (Ok(rows), Ok(cols)) => Ok((rows, cols)),
+                _ => Err(bad()),
+            }
+        };
+
+        let (topology, param_count) = match kind {
+            "line" => (Topology::Line { nodes: int(0)? }, 1),
+            "ring" => (Topology::Ring { nodes: int(0)? }, 1),
+            "star" => (Topology::Star { nodes: int(0)? }, 1),
+            "grid" => {
+                let (rows, cols) = size()?;
+                (Topology::Grid { rows, cols }, 1)
+            }
+            "torus" => {
+                let (rows, cols) = size()?;
+                (Topology::Torus { rows, cols }, 1)
+            }
+            "mesh" => (Topology::Mesh { nodes: int(0)? }, 1),
+            "tree" => (
+                Topology::Tree {
+                    nodes: int(0)?,
+                    fanout: int(1)?,
+                },
+                2,
+            ),
+            "er" => (
+                Topology::ErdosRenyi {
+                    nodes: int(0)?,
+                    p: float(1)?,
+                },
+                2,
+            ),
+            "ba" => (
+                Topology::BarabasiAlbert {
+                    nodes: int(0)?,
+                    m: int(1)?,
+                },
+                2,
+            ),
+            "waxman" => (
+                Topology::Waxman {
+                    nodes: int(0)?,
+                    alpha: float(1)?,
+                    beta: float(2)?,
+                },
+                3,
+            ),
+            _ => return Err(bad()),
+        };
+        match params.len() == param_count {
+            true => Ok(topology),
+            false => Err(bad()),
+        }
+    }
+}
+
+impl FromStr for QueueDelays {
+    type Err = ErrCode;
+
+    /// Parses a number of ticks, or `min..max` for delays drawn uniformly
+    /// between the two.
+    fn from_str(s: &str) -> Result<QueueDelays, ErrCode> {
+        let bad = || ErrCode::BadScenario(format!("bad queue delays {s}"));
+        match s.split_once("..") {
+            Some((min, max)) => match (min.parse(), max.parse()) {
+                (Ok(min), Ok(max)) if min <= max => Ok(QueueDelays::Uniform { min, max }),
+                _ => Err(bad()),
+            },
+            None => s.parse().map(QueueDelays::Constant).map_err(|_| bad()),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::{fmt::Display, io};
+
+use super::file_utils::NtwkErrCode;
+use crate::timeline::snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter};
+
+/// Extra latency a link adds to each packet on top of its transmission time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Jitter {
+    /// Whole number of ticks drawn uniformly from `0..=max`.
+    Uniform { max: u32 },
+    /// Magnitude of a normal draw with the given standard deviation,
+    /// rounded to the nearest tick.
+    Normal { stddev: f64 },
+}
+
+impl Jitter {
+    /// Parses `uniform:<max>` or `normal:<stddev>`.
+    fn parse(value: &str) -> Option<Jitter> {
+        let (kind, param) = value.split_once(':')?;
+        match kind {
+            "uniform" => param.parse().ok().map(|max| Jitter::Uniform { max }),
+            "normal" => match param.parse::<f64>() {
+                Ok(stddev) if stddev.is_finite() && stddev >= 0.0 => {
+                    Some(Jitter::Normal { stddev })
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// A one-way connection from the owning node to `to`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NtwkLink {
+    to: u32,
+    bandwidth: Option<u32>,
+    mtu: Option<u32>,
+    loss: f64,
+    jitter: Option<Jitter>,
+}
+
+impl NtwkLink {
+    pub fn new(to: u32) -> NtwkLink {
+        NtwkLink {
+            to,
+            bandwidth: None,
+            mtu: None,
+            loss: 0.0,
+            jitter: None,
+        }
+    }
+
+    /// Applies a single `key=value` attribute from a connection line.
+    pub fn set_attr(&mut self, key: &str, value: &str) -> Result<(), NtwkErrCode> {
+        match key {
+            "bw" => match value.parse::<u32>() {
+                Ok(bw) if bw > 0 => self.bandwidth = Some(bw),
+                _ => return Err(NtwkErrCode::BadLinkAttr(format!("{key}={value}"))),
+            },
+            "mtu" => match value.parse::<u32>() {
+                Ok(mtu) if mtu > 0 => self.mtu = Some(mtu),
+                _ => return Err(NtwkErrCode::BadLinkAttr(format!("{key}={value}"))),
+            },
+            "loss" => match value.parse::<f64>() {
+                Ok(loss) if (0.0..=1.0).contains(&loss) => self.loss = loss,
+                _ => return Err(NtwkErrCode::BadLinkAttr(format!("{key}={value}"))),
+            },
+            "jitter" => match Jitter::parse(value) {
+                Some(jitter) => self.jitter = Some(jitter),
+                None => return Err(NtwkErrCode::BadLinkAttr(format!("{key}={value}"))),
+            },
+            _ => return Err(NtwkErrCode::BadLinkAttr(format!("{key}={value}"))),
+        }
+        Ok(())
+    }
+
+    pub fn to(&self) -> u32 {
+        self.to
+    }
+
+    /// Largest packet, in bytes, the link carries without fragmenting it.
+    pub fn mtu(&self) -> Option<u32> {
+        self.mtu
+    }
+
+    /// Attributes that differ from a plain link, as `key=value` pairs in
+    /// the order a connection line would list them.
+    pub fn attrs(&self) -> Vec<String> {
+        let mut attrs = Vec::new();
+        if let Some(bw) = self.bandwidth {
+            attrs.push(format!("bw={bw}"));
+        }
+        if let Some(mtu) = self.mtu {
+            attrs.push(format!("mtu={mtu}"));
+        }
+        if self.loss > 0.0 {
+            attrs.push(format!("loss={}", self.loss));
+        }
+        if let Some(jitter) = self.jitter {
+            attrs.push(format!("jitter={jitter}"));
+        }
+        attrs
+    }
+
+    /// Probability that a packet sent across the link is lost.
+    pub fn loss(&self) -> f64 {
+        self.loss
+    }
+
+    pub fn jitter(&self) -> Option<Jitter> {
+        self.jitter
+    }
+
+    /// Number of ticks needed to push `size` bytes across this link.
+    ///
+    /// Links without a bandwidth take a single tick regardless of size.
+    pub fn tx_time(&self, size: u32) -> u32 {
+        match self.bandwidth {
+            Some(bw) => size.div_ceil(bw).max(1),
+            None => 1,
+        }
+    }
+}
+
+impl Display for Jitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Jitter::Uniform { max } => write!(f, "uniform:{max}"),
+            Jitter::Normal { stddev } => write!(f, "normal:{stddev}"),
+        }
+    }
+}
+
+impl Snap for NtwkLink {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.val(self.to)?;
+        out.opt(self.bandwidth)?;
+        out.opt(self.mtu)?;
+        out.val(self.loss)?;
+        out.opt(self.jitter)
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        let to = inp.val()?;
+        let (bandwidth, mtu, loss) = (inp.opt()?, inp.opt()?, inp.val()?);
+        let jitter = match inp.opt::<String>()? {
+            Some(jitter) => Some(Jitter::parse(&jitter).ok_or(SnapErrCode::BadToken)?),
+            None => None,
+        };
+        Ok(NtwkLink {
+            to,
+            bandwidth,
+            mtu,
+            loss,
+            jitter,
+        })
+    }
+}
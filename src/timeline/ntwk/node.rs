@@ -1,13 +1,17 @@
-use super::file_utils::NtwkErrCode;
+use std::io;
+
+use super::{file_utils::NtwkErrCode, link::NtwkLink};
+use crate::timeline::snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter};
 
 const MAX_CONN_COUNT: usize = 20;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct NtwkNode {
     id: u32,
     conn_count: usize,
-    conn_list: Vec<u32>,
+    conn_list: Vec<NtwkLink>,
     queue_delay: u32,
+    queue_capacity: Option<u32>,
 }
 
 impl NtwkNode {
@@ -20,6 +24,7 @@ impl NtwkNode {
             conn_count: cc,
             conn_list: Vec::with_capacity(cc),
             queue_delay,
+            queue_capacity: None,
         };
         if conn_count == 0 {
             create_node_status = NtwkErrCode::HasZeroConnections;
@@ -39,7 +44,45 @@ impl NtwkNode {
     pub fn conn_len(&self) -> usize {
         self.conn_list.len()
     }
-    pub fn push(&mut self, conn: u32) {
+    pub fn push(&mut self, conn: NtwkLink) {
         self.conn_list.push(conn);
     }
+    pub fn links(&self) -> &[NtwkLink] {
+        &self.conn_list
+    }
+    pub fn link_to(&self, id: u32) -> Option<&NtwkLink> {
+        self.conn_list.iter().find(|link| link.to() == id)
+    }
+    pub fn queue_delay(&self) -> u32 {
+        self.queue_delay
+    }
+    /// Maximum number of bytes the node can hold at once, if bounded.
+    pub fn queue_capacity(&self) -> Option<u32> {
+        self.queue_capacity
+    }
+    pub fn set_queue_capacity(&mut self, capacity: u32) {
+        self.queue_capacity = Some(capacity);
+    }
+}
+
+impl Snap for NtwkNode {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.tag("node")?;
+        out.val(self.id)?;
+        out.val(self.conn_count)?;
+        out.val(self.queue_delay)?;
+        out.opt(self.queue_capacity)?;
+        out.list(self.conn_list.iter())
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        inp.tag("node")?;
+        Ok(NtwkNode {
+            id: inp.val()?,
+            conn_count: inp.val()?,
+            queue_delay: inp.val()?,
+            queue_capacity: inp.opt()?,
+            conn_list: inp.list()?,
+        })
+    }
 }
@@ -0,0 +1,153 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    io,
+};
+
+use super::Ntwk;
+use crate::{
+    timeline::{
+        sim::instr::DEFAULT_MSG_SIZE,
+        snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter},
+    },
+    Routing,
+};
+
+/// Next-hop lookup for every reachable (source, destination) pair.
+#[derive(Debug, Default, PartialEq)]
+pub struct RouteTable {
+    next_hops: HashMap<(u32, u32), u32>,
+}
+
+impl RouteTable {
+    pub fn build(ntwk: &Ntwk, routing: Routing) -> RouteTable {
+        match routing {
+            Routing::Hops => Self::fewest_hops(ntwk),
+            Routing::Delay => Self::least_delay(ntwk),
+        }
+    }
+
+    /// Builds fewest-hop routes with a breadth-first search from every node.
+    ///
+    /// Ties are broken by the order connections appear in the `.ntwk` file,
+    /// so the same network always produces the same routes.
+    fn fewest_hops(ntwk: &Ntwk) -> RouteTable {
+        let mut table = RouteTable::default();
+
+        for src in ntwk.node_ids() {
+            // Maps each visited node to the first hop taken from `src` to reach it
+            let mut first_hop: HashMap<u32, u32> = HashMap::new();
+            let mut frontier: VecDeque<u32> = VecDeque::new();
+            frontier.push_back(src);
+
+            while let Some(curr) = frontier.pop_front() {
+                let node = match ntwk.node(curr) {
+                    Some(node) => node,
+                    None => continue,
+                };
+                for link in node.links() {
+                    let next = link.to();
+                    if next == src || first_hop.contains_key(&next) || ntwk.node(next).is_none() {
+                        continue;
+                    }
+                    let hop = if curr == src { next } else { first_hop[&curr] };
+                    first_hop.insert(next, hop);
+                    frontier.push_back(next);
+                }
+            }
+
+            for (dst, hop) in first_hop {
+                table.next_hops.insert((src, dst), hop);
+            }
+        }
+
+        table
+    }
+
+    /// Builds least-delay routes with Dijkstra's algorithm from every node.
+    ///
+    /// A hop costs the queue delay of the node forwarding the packet plus
+    /// the time the link takes to carry a message of the default size.
+    /// Ties go to the route found first, as with fewest-hop routes.
+    fn least_delay(ntwk: &Ntwk) -> RouteTable {
+        let mut table = RouteTable::default();
+
+        for src in ntwk.node_ids() {
+            // Maps each reached node to its cheapest cost so far and the
+            // first hop taken from `src` along that route
+            let mut best: HashMap<u32, (u64, u32)> = HashMap::new();
+            let mut settled: HashSet<u32> = HashSet::new();
+            // Ordered by cost, then by when the node was reached
+            let mut frontier: BinaryHeap<Reverse<(u64, u64, u32)>> = BinaryHeap::new();
+            let mut reached = 0;
+            frontier.push(Reverse((0, reached, src)));
+
+            while let Some(Reverse((cost, _, curr))) = frontier.pop() {
+                if !settled.insert(curr) {
+                    continue;
+                }
+                let node = match ntwk.node(curr) {
+                    Some(node) => node,
+                    None => continue,
+                };
+                for link in node.links() {
+                    let next = link.to();
+                    if next == src || settled.contains(&next) || ntwk.node(next).is_none() {
+                        continue;
+                    }
+                    let hop_cost = node.queue_delay() + link.tx_time(DEFAULT_MSG_SIZE);
+                    let next_cost = cost + hop_cost as u64;
+                    if best
+                        .get(&next)
+                        .is_some_and(|&(known, _)| known <= next_cost)
+                    {
+                        continue;
+                    }
+                    let hop = if curr == src { next } else { best[&curr].1 };
+                    best.insert(next, (next_cost, hop));
+                    reached += 1;
+                    frontier.push(Reverse((next_cost, reached, next)));
+                }
+            }
+
+            for (dst, (_, hop)) in best {
+                table.next_hops.insert((src, dst), hop);
+            }
+        }
+
+        table
+    }
+
+    pub fn next_hop(&self, from: u32, to: u32) -> Option<u32> {
+        self.next_hops.get(&(from, to)).copied()
+    }
+}
+
+impl Snap for RouteTable {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        // Sorted, so the same routes are always written the same way
+        let mut routes: Vec<_> = self.next_hops.iter().collect();
+        routes.sort();
+        out.tag("routes")?;
+        out.val(routes.len())?;
+        for (&(from, to), next) in routes {
+            out.tag("route")?;
+            out.val(from)?;
+            out.val(to)?;
+            out.val(next)?;
+        }
+        Ok(())
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        inp.tag("routes")?;
+        let count: usize = inp.val()?;
+        let mut table = RouteTable::default();
+        for _ in 0..count {
+            inp.tag("route")?;
+            let (from, to, next) = (inp.val()?, inp.val()?, inp.val()?);
+            table.next_hops.insert((from, to), next);
+        }
+        Ok(table)
+    }
+}
@@ -0,0 +1,49 @@
+use std::io::{self, Write};
+
+use super::Ntwk;
+
+impl Ntwk {
+    /// Writes the network in the `.ntwk` format, which reads back with the
+    /// same nodes and links.
+    ///
+    /// Nodes and their links come out in the order they were read or
+    /// built, so routes that break ties by that order don't change. Routes
+    /// themselves aren't written: the network read back has fewest-hop
+    /// routes, and only equals this one if it had them too.
+    ///
+    /// ```
+    /// # use guwah::Ntwk;
+    /// let ntwk = Ntwk::from_file("config/basic.ntwk")?;
+    /// let mut text = Vec::new();
+    /// ntwk.write_ntwk(&mut text).unwrap();
+    /// assert_eq!(Ntwk::from_reader(text.as_slice())?, ntwk);
+    /// # Ok::<(), guwah::ErrCode>(())
+    /// ```
+    pub fn write_ntwk<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for node in self.node_list.iter().map(|node| node.borrow()) {
+            match node.queue_capacity() {
+                Some(capacity) => writeln!(
+                    out,
+                    "{},{},{},{capacity}",
+                    node.id(),
+                    node.conn_len(),
+                    node.queue_delay()
+                )?,
+                None => writeln!(
+                    out,
+                    "{},{},{}",
+                    node.id(),
+                    node.conn_len(),
+                    node.queue_delay()
+                )?,
+            }
+            for link in node.links() {
+                let mut fields = vec![link.to().to_string()];
+                fields.extend(link.attrs());
+                writeln!(out, "{}", fields.join(","))?;
+            }
+            writeln!(out, "endNode")?;
+        }
+        writeln!(out, "endNet")
+    }
+}
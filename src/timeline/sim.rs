@@ -1,12 +1,21 @@
 use self::{file_utils::SimErrCode, instr::Instr};
-use crate::timeline::utils;
+use crate::{timeline::utils, ErrCode};
+use std::{
+    fmt::Display,
+    io::{self, BufRead},
+    str::FromStr,
+};
 
+pub(crate) mod builder;
+pub(crate) mod check;
 mod file_utils;
 pub(crate) mod instr;
+pub(crate) mod workload;
+mod writer;
 
 const DEFAULT_NUM_INSTRS: usize = 10;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct InstrSet(Vec<Instr>);
 
 impl InstrSet {
@@ -53,37 +62,61 @@ impl<'a> Iterator for InstrAsIter<'a> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Sim {
     instr_set_set: Vec<InstrSet>,
+    seed: Option<u64>,
 }
 
 impl Sim {
-    pub fn from_file(filename: &String) -> Result<Sim, SimErrCode> {
-        let mut status = SimErrCode::Okay;
-
+    /// Reads the instructions in a `.sim` file, skipping lines that
+    /// aren't a valid instruction.
+    pub fn from_file(filename: &str) -> Result<Sim, ErrCode> {
         let lines = match utils::internal_utils::read_lines(filename) {
             Ok(lines) => lines,
             Err(e) => {
-                eprintln!("{e}");
-                status = SimErrCode::BadFile;
-                return Err(status);
+                return Err(ErrCode::SimFileParseFailed {
+                    file: filename.to_string(),
+                    reason: format!("{}: {e}", SimErrCode::BadFile),
+                })
             }
         };
+        Sim::parse(lines).map_err(|status| status.into_err(filename))
+    }
+
+    /// Reads instructions in the `.sim` format from `reader`.
+    ///
+    /// ```
+    /// # use guwah::Sim;
+    /// let sim: Sim = "0,seed,7\n1,msg,1,1,2\n5,endSim\n".parse()?;
+    /// assert_eq!(sim.seed(), Some(7));
+    /// # Ok::<(), guwah::ErrCode>(())
+    /// ```
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Sim, ErrCode> {
+        Sim::parse(reader.lines())
+            .map_err(|status| status.into_err(utils::internal_utils::READER_NAME))
+    }
 
+    fn parse(lines: impl Iterator<Item = io::Result<String>>) -> Result<Sim, SimErrCode> {
         let mut sims = Sim {
             instr_set_set: Vec::with_capacity(DEFAULT_NUM_INSTRS),
+            seed: None,
         };
 
         let mut prev_timestamp: u32 = 0;
         let mut curr_idx: u32 = 0;
         for line in lines {
             let s = line.unwrap_or("".to_string());
-            let s = utils::internal_utils::strip_comment(s);
+            let s = utils::internal_utils::clean_line(&s);
             if s.is_empty() {
                 continue;
             };
 
+            if let Some(seed) = file_utils::parse_seed(&s) {
+                sims.seed = Some(seed);
+                continue;
+            }
+
             let instr = match file_utils::parse_instr(&s) {
                 Ok(instr) => instr,
                 Err(_) => continue,
@@ -92,8 +125,15 @@ impl Sim {
             sims.insert(instr, &mut prev_timestamp, &mut curr_idx);
         }
 
+        sims.finish()
+    }
+
+    /// Checks that the instructions end with an endSim instruction.
+    fn finish(mut self) -> Result<Sim, SimErrCode> {
+        let mut status = SimErrCode::Okay;
+
         // Ensure that last item in list is an endSim instruction
-        let maybe_instr_set = sims.instr_set_set.last_mut();
+        let maybe_instr_set = self.instr_set_set.last_mut();
         if let Some(instr_set) = maybe_instr_set {
             let vec_ref = instr_set.as_mut_vec();
             vec_ref.shrink_to_fit();
@@ -107,11 +147,16 @@ impl Sim {
         }
 
         match status {
-            SimErrCode::Okay => Ok(sims),
+            SimErrCode::Okay => Ok(self),
             _ => Err(status),
         }
     }
 
+    /// Seed set by a `seed` line in the file, if any.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
     fn insert(&mut self, instr: Instr, prev_timestamp: &mut u32, curr_idx: &mut u32) {
         if instr.timestamp() != *prev_timestamp {
             *prev_timestamp = instr.timestamp();
@@ -140,28 +185,33 @@ impl Sim {
     ///
     /// Example using `enumerate`
     /// ```
-    /// let sims = Sim::from_file("sample.sim");
+    /// # use guwah::Sim;
+    /// let sims = Sim::from_file("config/basic.sim")?;
     /// for (timestep, instr_set) in sims.into_timeline().enumerate() {
     ///     if let Some(instr_set) = instr_set {
     ///         for instr in instr_set.iter() {
     ///             assert!((instr.timestamp() as usize) == timestep);
-    ///         }   
+    ///         }
     ///     }
     /// }
+    /// # Ok::<(), guwah::ErrCode>(())
     /// ```
     ///
     /// Example using `Iterator::for_each` and `zip` to compare timestamp with `u32` values
     /// ```
-    /// let sims = Sim::from_file("sample.sim");
+    /// # use guwah::Sim;
+    /// let sims = Sim::from_file("config/basic.sim")?;
     /// let start: u32 = 0;
     /// (start..).zip(sims.into_timeline()).for_each(|(timestep, maybe_instr_set)| {
     ///     if let Some(instr_set) = maybe_instr_set {
     ///         for instr in instr_set.iter() {
     ///             assert!(instr.timestamp() == timestep);
-    ///         }   
+    ///         }
     ///     }
     /// });
+    /// # Ok::<(), guwah::ErrCode>(())
     /// ```
+    #[allow(clippy::wrong_self_convention)]
     pub const fn into_timeline<'a>(&'a self) -> SimIntoTimeline<'a> {
         SimIntoTimeline {
             sim: self,
@@ -197,10 +247,7 @@ impl<'a> Iterator for SimIntoTimeline<'a> {
     type Item = Option<&'a InstrSet>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let result = match self.sim.instr_set_set.get(self.index) {
-            Some(rc) => rc,
-            None => return None,
-        };
+        let result = self.sim.instr_set_set.get(self.index)?;
 
         let ret: Self::Item;
         let curr_timestamp = result.0[0].timestamp();
@@ -215,3 +262,41 @@ impl<'a> Iterator for SimIntoTimeline<'a> {
         Some(ret)
     }
 }
+
+impl FromStr for Sim {
+    type Err = ErrCode;
+
+    fn from_str(s: &str) -> Result<Sim, ErrCode> {
+        Sim::from_reader(s.as_bytes())
+    }
+}
+
+impl SimErrCode {
+    fn into_err(self, filename: &str) -> ErrCode {
+        ErrCode::SimFileParseFailed {
+            file: filename.to_string(),
+            reason: self.to_string(),
+        }
+    }
+}
+
+impl Display for SimErrCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            SimErrCode::Okay => "no error",
+            SimErrCode::MismatchEndSimToken => "last instruction isn't endSim",
+            SimErrCode::UnknownInstrKind => "unknown instruction",
+            SimErrCode::NoId => "instruction without a message ID",
+            SimErrCode::NoTimestamp => "instruction without a timestamp",
+            SimErrCode::NoStartNode => "instruction without a start node",
+            SimErrCode::NoEndNode => "instruction without an end node",
+            SimErrCode::BadFile => "file can't be read",
+            SimErrCode::BadLine => "malformed line",
+            SimErrCode::EmptyContents => "no instructions",
+            SimErrCode::BadOption => "bad instruction option",
+            SimErrCode::PayloadTooLarge => "payload larger than the message",
+            SimErrCode::BadGenerator => "bad flow or poisson parameters",
+        };
+        write!(f, "{reason}")
+    }
+}
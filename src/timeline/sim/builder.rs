@@ -0,0 +1,180 @@
+use super::{
+    file_utils::SimErrCode,
+    instr::{
+        Arrivals, BcastInstr, EndInstr, FlowInstr, Instr, McastInstr, MsgInstr, RepInstr,
+        SnapshotInstr,
+    },
+    Sim, DEFAULT_NUM_INSTRS,
+};
+use crate::ErrCode;
+
+/// Builds a [`Sim`] in code rather than reading it from a `.sim` file.
+///
+/// Instructions can be added in any order; they're sorted by timestamp
+/// when the sim is built. Methods chain, and the first mistake is kept and
+/// returned by [`build`](SimBuilder::build):
+///
+/// ```
+/// # use guwah::{NtwkBuilder, SimBuilder, Simulator};
+/// let ntwk = NtwkBuilder::new()
+///     .node(1, 1)
+///     .link(1, 2)
+///     .node(2, 1)
+///     .link(2, 1)
+///     .build()?;
+/// let sim = SimBuilder::new()
+///     .msg(0, 1, 1, 2)
+///     .opt("size", "128")
+///     .rep(20, None)
+///     .end(20)
+///     .build()?;
+/// let result = Simulator::new(ntwk, sim).run()?;
+/// assert_eq!(result.reports().len(), 1);
+/// # Ok::<(), guwah::ErrCode>(())
+/// ```
+#[derive(Default)]
+pub struct SimBuilder {
+    instrs: Vec<Instr>,
+    seed: Option<u64>,
+    error: Option<String>,
+}
+
+impl SimBuilder {
+    pub fn new() -> SimBuilder {
+        SimBuilder::default()
+    }
+
+    /// Seeds the random draws, as a `seed` line would.
+    pub fn seed(&mut self, seed: u64) -> &mut SimBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sends message `id` from `src` to `dst` at tick `t`.
+    pub fn msg(&mut self, t: u32, id: u32, src: u32, dst: u32) -> &mut SimBuilder {
+        self.push(Instr::Msg(MsgInstr::new(t, id, src, dst)))
+    }
+
+    /// Sends message `id` from `src` to every other node at tick `t`.
+    pub fn bcast(&mut self, t: u32, id: u32, src: u32) -> &mut SimBuilder {
+        self.push(Instr::Bcast(BcastInstr::new(t, id, src)))
+    }
+
+    /// Sends message `id` from `src` to each of `dsts` at tick `t`.
+    pub fn mcast(&mut self, t: u32, id: u32, src: u32, dsts: &[u32]) -> &mut SimBuilder {
+        if dsts.is_empty() {
+            self.fail(t, "mcast", SimErrCode::NoEndNode);
+        }
+        self.push(Instr::Mcast(McastInstr::new(t, id, src, dsts.to_vec())))
+    }
+
+    /// Sends `count` messages from `src` to `dst`, one every `interval`
+    /// ticks from tick `t`, numbered up from `id`.
+    pub fn flow(
+        &mut self,
+        t: u32,
+        id: u32,
+        src: u32,
+        dst: u32,
+        interval: u32,
+        count: u32,
+    ) -> &mut SimBuilder {
+        if interval == 0 {
+            self.fail(t, "flow", SimErrCode::BadGenerator);
+        }
+        let arrivals = Arrivals::Constant { interval };
+        let flow = FlowInstr::new(MsgInstr::new(t, id, src, dst), arrivals, count);
+        if flow.last_id().is_none() {
+            self.fail(t, "flow", SimErrCode::BadGenerator);
+        }
+        self.push(Instr::Flow(flow))
+    }
+
+    /// Like [`flow`](SimBuilder::flow), but with Poisson arrivals at `rate`
+    /// messages per tick.
+    pub fn poisson(
+        &mut self,
+        t: u32,
+        id: u32,
+        src: u32,
+        dst: u32,
+        rate: f64,
+        count: u32,
+    ) -> &mut SimBuilder {
+        if rate.is_nan() || rate <= 0.0 {
+            self.fail(t, "poisson", SimErrCode::BadGenerator);
+        }
+        let arrivals = Arrivals::Poisson { rate, seed: None };
+        let flow = FlowInstr::new(MsgInstr::new(t, id, src, dst), arrivals, count);
+        if flow.last_id().is_none() {
+            self.fail(t, "poisson", SimErrCode::BadGenerator);
+        }
+        self.push(Instr::Flow(flow))
+    }
+
+    /// Applies a `key=value` option, such as `size` or `reliable`, to the
+    /// instruction added last.
+    pub fn opt(&mut self, key: &str, value: &str) -> &mut SimBuilder {
+        let result = match self.instrs.last_mut() {
+            Some(instr) => instr
+                .set_option(key, value)
+                .map_err(|e| (instr.timestamp(), instr.name(), e)),
+            None => Err((0, "option", SimErrCode::BadOption)),
+        };
+        if let Err((t, name, e)) = result {
+            self.fail(t, name, e);
+        }
+        self
+    }
+
+    /// Reports on message `id`, or on every message if `None`, at tick `t`.
+    pub fn rep(&mut self, t: u32, id: Option<u32>) -> &mut SimBuilder {
+        self.push(Instr::Rep(RepInstr::new(t, id)))
+    }
+
+    /// Saves the state of the run to `filename` at tick `t`.
+    pub fn snapshot(&mut self, t: u32, filename: &str) -> &mut SimBuilder {
+        if filename.is_empty() {
+            self.fail(t, "snapshot", SimErrCode::BadLine);
+        }
+        self.push(Instr::Snapshot(SnapshotInstr::new(t, filename.to_string())))
+    }
+
+    /// Ends the run at tick `t`.
+    pub fn end(&mut self, t: u32) -> &mut SimBuilder {
+        self.push(Instr::EndSim(EndInstr::new(t)))
+    }
+
+    /// Puts the sim together; like a sim file, it must end with
+    /// [`end`](SimBuilder::end).
+    pub fn build(&self) -> Result<Sim, ErrCode> {
+        if let Some(reason) = &self.error {
+            return Err(ErrCode::BadScenario(reason.clone()));
+        }
+
+        let mut instrs = self.instrs.clone();
+        // Stable, so instructions at the same tick keep the order they were added in
+        instrs.sort_by_key(Instr::timestamp);
+
+        let mut sims = Sim {
+            instr_set_set: Vec::with_capacity(DEFAULT_NUM_INSTRS),
+            seed: self.seed,
+        };
+        let mut prev_timestamp: u32 = 0;
+        let mut curr_idx: u32 = 0;
+        for instr in instrs {
+            sims.insert(instr, &mut prev_timestamp, &mut curr_idx);
+        }
+        sims.finish()
+            .map_err(|e| ErrCode::BadScenario(e.to_string()))
+    }
+
+    fn push(&mut self, instr: Instr) -> &mut SimBuilder {
+        self.instrs.push(instr);
+        self
+    }
+
+    fn fail(&mut self, t: u32, name: &str, e: SimErrCode) {
+        self.error.get_or_insert(format!("{name} at t={t}: {e}"));
+    }
+}
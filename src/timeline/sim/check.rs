@@ -0,0 +1,210 @@
+use std::{collections::HashMap, fmt::Display};
+
+use super::{
+    instr::{Arrivals, Instr},
+    Sim,
+};
+use crate::timeline::ntwk::Ntwk;
+
+/// Something in a sim that doesn't fit the network it runs over, or
+/// contradicts itself, found by [`Sim::check`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimProblem {
+    /// Message `msg` starts or ends at a node the network doesn't have.
+    UnknownNode { msg: u32, node: u32 },
+    /// Message `msg` is sent from `node` to itself.
+    SameEnds { msg: u32, node: u32 },
+    /// Message ID `msg` is sent at tick `first` and again at `again`. Both
+    /// messages are reported, the later as `#2`, but `rep` with the ID
+    /// shows them together.
+    DuplicateId { msg: u32, first: u32, again: u32 },
+    /// The `rep` at tick `tick` asks about message `msg`, which hasn't
+    /// been sent by then.
+    UnknownReport { msg: u32, tick: u32 },
+    /// The flow numbering its messages up from `flow` gives one of them
+    /// ID `msg`, which another message or flow uses too.
+    FlowOverlap { flow: u32, msg: u32 },
+}
+
+/// The IDs a flow numbers its messages with, and when it sends them.
+struct FlowIds {
+    first: u32,
+    last: u32,
+    start: u32,
+    /// Ticks between messages, 0 for Poisson flows, whose send times are
+    /// only known once the run draws them.
+    interval: u32,
+}
+
+impl FlowIds {
+    fn contains(&self, msg: u32) -> bool {
+        (self.first..=self.last).contains(&msg)
+    }
+
+    /// Tick the flow's message `msg` goes out at, at the earliest.
+    fn sent_at(&self, msg: u32) -> u32 {
+        self.start
+            .saturating_add((msg - self.first).saturating_mul(self.interval))
+    }
+}
+
+impl Sim {
+    /// Checks the sim against `ntwk` before it runs, returning every
+    /// problem found in the order the instructions come.
+    ///
+    /// ```
+    /// # use guwah::{Ntwk, Sim, SimProblem};
+    /// let ntwk = Ntwk::from_file("config/basic.ntwk")?;
+    /// let sim = Sim::from_file("config/basic.sim")?;
+    /// let problems = sim.check(&ntwk);
+    /// assert!(problems.contains(&SimProblem::DuplicateId { msg: 6, first: 14, again: 18 }));
+    /// # Ok::<(), guwah::ErrCode>(())
+    /// ```
+    pub fn check(&self, ntwk: &Ntwk) -> Vec<SimProblem> {
+        let mut problems = Vec::new();
+        // Tick each message ID is first sent at, besides those of flows
+        let mut sent: HashMap<u32, u32> = HashMap::new();
+        let mut flows: Vec<FlowIds> = Vec::new();
+        let check_node = |problems: &mut Vec<SimProblem>, msg: u32, node: u32| {
+            if ntwk.node(node).is_none() {
+                problems.push(SimProblem::UnknownNode { msg, node });
+            }
+        };
+
+        for instr in self.instr_set_set.iter().flat_map(|set| set.iter()) {
+            let tick = instr.timestamp();
+            // Messages the instruction sends, with the tick each goes out at
+            let msgs: Vec<(u32, u32)> = match instr {
+                Instr::Msg(m) => {
+                    check_node(&mut problems, m.msg_id(), m.start_node());
+                    check_node(&mut problems, m.msg_id(), m.end_node());
+                    if m.start_node() == m.end_node() {
+                        problems.push(SimProblem::SameEnds {
+                            msg: m.msg_id(),
+                            node: m.start_node(),
+                        });
+                    }
+                    vec![(m.msg_id(), tick)]
+                }
+                Instr::Bcast(b) => {
+                    check_node(&mut problems, b.msg_id(), b.start_node());
+                    vec![(b.msg_id(), tick)]
+                }
+                Instr::Mcast(m) => {
+                    check_node(&mut problems, m.msg_id(), m.start_node());
+                    for &node in m.end_nodes() {
+                        check_node(&mut problems, m.msg_id(), node);
+                        if node == m.start_node() {
+                            problems.push(SimProblem::SameEnds {
+                                msg: m.msg_id(),
+                                node,
+                            });
+                        }
+                    }
+                    vec![(m.msg_id(), tick)]
+                }
+                Instr::Flow(f) => {
+                    let template = f.template();
+                    check_node(&mut problems, f.msg_id(), template.start_node());
+                    check_node(&mut problems, f.msg_id(), template.end_node());
+                    if template.start_node() == template.end_node() {
+                        problems.push(SimProblem::SameEnds {
+                            msg: f.msg_id(),
+                            node: template.start_node(),
+                        });
+                    }
+                    if f.count() > 0 {
+                        let ids = FlowIds {
+                            first: f.msg_id(),
+                            last: f.last_id().unwrap_or(u32::MAX),
+                            start: tick,
+                            interval: match f.arrivals() {
+                                Arrivals::Constant { interval } => interval,
+                                Arrivals::Poisson { .. } => 0,
+                            },
+                        };
+                        let mut taken: Vec<u32> = sent
+                            .keys()
+                            .copied()
+                            .filter(|&msg| ids.contains(msg))
+                            .collect();
+                        taken.sort_unstable();
+                        // Overlapping flows share every ID from the higher start
+                        taken.extend(
+                            flows
+                                .iter()
+                                .filter(|other| other.first <= ids.last && ids.first <= other.last)
+                                .map(|other| other.first.max(ids.first)),
+                        );
+                        problems.extend(taken.into_iter().map(|msg| SimProblem::FlowOverlap {
+                            flow: ids.first,
+                            msg,
+                        }));
+                        flows.push(ids);
+                    }
+                    Vec::new()
+                }
+                Instr::Rep(r) => {
+                    if let Some(msg) = r.msg_id() {
+                        let flow_at = flows
+                            .iter()
+                            .filter(|ids| ids.contains(msg))
+                            .map(|ids| ids.sent_at(msg))
+                            .min();
+                        let at = sent.get(&msg).copied().into_iter().chain(flow_at).min();
+                        if at.is_none_or(|at| at > tick) {
+                            problems.push(SimProblem::UnknownReport { msg, tick });
+                        }
+                    }
+                    Vec::new()
+                }
+                Instr::Snapshot(_) | Instr::EndSim(_) => Vec::new(),
+            };
+
+            for (msg, at) in msgs {
+                for ids in flows.iter().filter(|ids| ids.contains(msg)) {
+                    problems.push(SimProblem::FlowOverlap {
+                        flow: ids.first,
+                        msg,
+                    });
+                }
+                match sent.get(&msg) {
+                    Some(&first) => problems.push(SimProblem::DuplicateId {
+                        msg,
+                        first,
+                        again: at,
+                    }),
+                    None => {
+                        sent.insert(msg, at);
+                    }
+                }
+            }
+        }
+        problems
+    }
+}
+
+impl Display for SimProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimProblem::UnknownNode { msg, node } => {
+                write!(f, "msg {msg} uses node {node}, which isn't in the network")
+            }
+            SimProblem::SameEnds { msg, node } => {
+                write!(f, "msg {msg} is sent from node {node} to itself")
+            }
+            SimProblem::DuplicateId { msg, first, again } => {
+                write!(f, "msg {msg} is sent at t={first} and again at t={again}")
+            }
+            SimProblem::UnknownReport { msg, tick } => {
+                write!(
+                    f,
+                    "rep at t={tick} asks about msg {msg}, which isn't sent by then"
+                )
+            }
+            SimProblem::FlowOverlap { flow, msg } => {
+                write!(f, "flow from msg {flow} reuses msg ID {msg}")
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use super::instr::{Instr, MsgInstr, RepInstr, EndInstr};
+use super::instr::{Arrivals, BcastInstr, EndInstr, FlowInstr, Instr, McastInstr, MsgInstr, RepInstr, SnapshotInstr};
+use crate::timeline::utils::internal_utils::split_attr;
 
 #[derive(PartialEq)]
 pub enum SimErrCode {
@@ -12,14 +13,28 @@ pub enum SimErrCode {
     BadFile,
     BadLine,
     EmptyContents,
+    BadOption,
+    PayloadTooLarge,
+    BadGenerator,
 }
 
-pub fn parse_instr(str: &String) -> Result<Instr, SimErrCode> {
+/// Reads a `timestamp,seed,value` line, which sets the simulation seed
+/// rather than scheduling an instruction.
+pub fn parse_seed(str: &str) -> Option<u64> {
+    let mut tokens = str.split(',').skip(1);
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some("seed"), Some(seed), None) => parse_tok(seed),
+        _ => None,
+    }
+}
+
+pub fn parse_instr(str: &str) -> Result<Instr, SimErrCode> {
     let mut time: Option<u32> = None;
     let mut kind: Option<String> = None;
     let mut id: Option<u32> = None;
     let mut start_node: Option<u32> = None;
-    let mut end_node: Option<u32> = None;
+    // Everything after the start node, read differently by each kind
+    let mut rest: Vec<&str> = Vec::new();
     let tokens = str.split(',');
 
     for (idx, tok) in tokens.enumerate() {
@@ -28,8 +43,7 @@ pub fn parse_instr(str: &String) -> Result<Instr, SimErrCode> {
             1 => kind = parse_tok(tok),
             2 => id = parse_tok(tok),
             3 => start_node = parse_tok(tok),
-            4 => end_node = parse_tok(tok),
-            _ => break
+            _ => rest.push(tok),
         }
     }
 
@@ -41,11 +55,17 @@ pub fn parse_instr(str: &String) -> Result<Instr, SimErrCode> {
         match str_match.as_str() {
             "msg" => if let Some(msg_id_match) = id {
                 if let Some(start_node_match) = start_node {
+                    let end_node = rest.first().and_then(|tok| parse_tok(tok));
                     if let Some(end_node_match) = end_node {
-                        instr = Instr::Msg(MsgInstr::new(time.unwrap(), 
-                                                         msg_id_match, 
-                                                         start_node_match, 
-                                                         end_node_match))
+                        let mut msg = MsgInstr::new(time.unwrap(), 
+                                                    msg_id_match, 
+                                                    start_node_match, 
+                                                    end_node_match);
+                        for opt in &rest[1..] {
+                            let (key, value) = split_attr(opt);
+                            msg.set_option(key, value)?;
+                        }
+                        instr = Instr::Msg(msg)
                     } else {
                         return Err(SimErrCode::NoEndNode)
                     }
@@ -55,7 +75,72 @@ pub fn parse_instr(str: &String) -> Result<Instr, SimErrCode> {
             } else {
                 return Err(SimErrCode::NoId)
             },
+            "bcast" => {
+                let msg_id_match = id.ok_or(SimErrCode::NoId)?;
+                let start_node_match = start_node.ok_or(SimErrCode::NoStartNode)?;
+                let mut bcast = BcastInstr::new(time.unwrap(), msg_id_match, start_node_match);
+                for opt in &rest {
+                    let (key, value) = split_attr(opt);
+                    bcast.set_option(key, value)?;
+                }
+                instr = Instr::Bcast(bcast)
+            },
+            "mcast" => {
+                let msg_id_match = id.ok_or(SimErrCode::NoId)?;
+                let start_node_match = start_node.ok_or(SimErrCode::NoStartNode)?;
+                // Destinations are every plain node ID before the first option
+                let end_nodes: Vec<u32> = rest.iter().map_while(|tok| parse_tok(tok)).collect();
+                if end_nodes.is_empty() {
+                    return Err(SimErrCode::NoEndNode)
+                }
+                let mut mcast = McastInstr::new(time.unwrap(), msg_id_match, start_node_match, end_nodes);
+                for opt in &rest[mcast.end_nodes().len()..] {
+                    let (key, value) = split_attr(opt);
+                    mcast.set_option(key, value)?;
+                }
+                instr = Instr::Mcast(mcast)
+            },
+            "flow" | "poisson" => {
+                let msg_id_match = id.ok_or(SimErrCode::NoId)?;
+                let start_node_match = start_node.ok_or(SimErrCode::NoStartNode)?;
+                let end_node_match = rest.first().and_then(|tok| parse_tok(tok)).ok_or(SimErrCode::NoEndNode)?;
+                let template = MsgInstr::new(time.unwrap(), msg_id_match, start_node_match, end_node_match);
+
+                // flow: dst,interval,count    poisson: dst,rate,count[,seed]
+                let (arrivals, count, arg_count) = match str_match.as_str() {
+                    "flow" => {
+                        let interval: u32 = rest.get(1).and_then(|tok| parse_tok(tok)).ok_or(SimErrCode::BadGenerator)?;
+                        if interval == 0 { return Err(SimErrCode::BadGenerator) };
+                        let count: u32 = rest.get(2).and_then(|tok| parse_tok(tok)).ok_or(SimErrCode::BadGenerator)?;
+                        (Arrivals::Constant { interval }, count, 3)
+                    },
+                    _ => {
+                        let rate: f64 = rest.get(1).and_then(|tok| parse_tok(tok)).ok_or(SimErrCode::BadGenerator)?;
+                        if rate.is_nan() || rate <= 0.0 { return Err(SimErrCode::BadGenerator) };
+                        let count: u32 = rest.get(2).and_then(|tok| parse_tok(tok)).ok_or(SimErrCode::BadGenerator)?;
+                        let seed: Option<u64> = rest.get(3).and_then(|tok| parse_tok(tok));
+                        (Arrivals::Poisson { rate, seed }, count, if seed.is_some() { 4 } else { 3 })
+                    },
+                };
+
+                let mut flow = FlowInstr::new(template, arrivals, count);
+                // Every message of the flow needs an ID of its own
+                if flow.last_id().is_none() { return Err(SimErrCode::BadGenerator) };
+                for opt in &rest[arg_count..] {
+                    let (key, value) = split_attr(opt);
+                    flow.set_option(key, value)?;
+                }
+                instr = Instr::Flow(flow)
+            },
             "rep" => instr = Instr::Rep(RepInstr::new(time.unwrap(), id)),
+            "snapshot" => {
+                // The third field is a filename, not an ID
+                let filename = str.split(',').nth(2).unwrap_or("");
+                if filename.is_empty() {
+                    return Err(SimErrCode::BadLine)
+                }
+                instr = Instr::Snapshot(SnapshotInstr::new(time.unwrap(), filename.to_string()))
+            },
             "endSim" => instr = Instr::EndSim(EndInstr::new(time.unwrap())),
             _ => return Err(SimErrCode::UnknownInstrKind),
         }
@@ -68,8 +153,5 @@ pub fn parse_instr(str: &String) -> Result<Instr, SimErrCode> {
 
 
 fn parse_tok<T: std::str::FromStr>(tok: &str) -> Option<T> {
-    match tok.parse::<T>() {
-        Ok(t) => Some(t),
-        Err(_) => None,
-    }
+    tok.parse::<T>().ok()
 }
\ No newline at end of file
@@ -1,24 +1,177 @@
-#[derive(Debug)]
+use std::io;
+
+use super::file_utils::SimErrCode;
+use crate::timeline::snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter};
+
+/// Size in bytes given to messages that don't specify one.
+pub const DEFAULT_MSG_SIZE: u32 = 64;
+/// Ticks a reliable message waits for its first ACK before resending.
+pub const DEFAULT_RTO: u32 = 20;
+/// Times a reliable message is resent before it's given up on.
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// Retransmission settings for a message sent in reliable mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reliability {
+    rto: u32,
+    retries: u32,
+}
+
+impl Reliability {
+    /// Initial retransmission timeout; it doubles after every resend.
+    pub fn rto(&self) -> u32 {
+        self.rto
+    }
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+}
+
+impl Default for Reliability {
+    fn default() -> Self {
+        Reliability {
+            rto: DEFAULT_RTO,
+            retries: DEFAULT_RETRIES,
+        }
+    }
+}
+
+/// Size and payload of a message, shared by every instruction that sends one.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Payload {
+    size: Option<u32>,
+    data: Option<Vec<u8>>,
+}
+
+impl Payload {
+    /// Applies a `size=<bytes>` or `data=<text>` option.
+    ///
+    /// Returns `Ok(false)` if `key` isn't a payload option, so callers can
+    /// go on to check their own options.
+    pub fn set_option(&mut self, key: &str, value: &str) -> Result<bool, SimErrCode> {
+        match key {
+            "size" => match value.parse::<u32>() {
+                Ok(size) => self.size = Some(size),
+                Err(_) => return Err(SimErrCode::BadOption),
+            },
+            "data" => self.data = Some(value.as_bytes().to_vec()),
+            _ => return Ok(false),
+        }
+
+        match (self.size, self.data.as_ref()) {
+            (Some(size), Some(data)) if (size as usize) < data.len() => {
+                Err(SimErrCode::PayloadTooLarge)
+            }
+            _ => Ok(true),
+        }
+    }
+
+    /// Size in bytes used for transmission time and queue capacity.
+    ///
+    /// A message with a payload but no explicit size is as large as its
+    /// payload.
+    pub fn size(&self) -> u32 {
+        match (self.size, self.data.as_ref()) {
+            (Some(size), _) => size,
+            (None, Some(data)) => data.len() as u32,
+            (None, None) => DEFAULT_MSG_SIZE,
+        }
+    }
+
+    pub fn data(&self) -> Option<&[u8]> {
+        self.data.as_deref()
+    }
+
+    /// Options that were set, as `key=value` pairs in the order an
+    /// instruction line would list them.
+    pub fn options(&self) -> Vec<String> {
+        let mut options = Vec::new();
+        if let Some(size) = self.size {
+            options.push(format!("size={size}"));
+        }
+        if let Some(data) = &self.data {
+            options.push(format!("data={}", String::from_utf8_lossy(data)));
+        }
+        options
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct MsgInstr {
     timestamp: u32,
     msg_id: u32,
     start_node: u32,
     end_node: u32,
+    payload: Payload,
+    reliability: Option<Reliability>,
+}
+
+/// How a traffic generator spaces out the messages it sends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arrivals {
+    /// One message every `interval` ticks.
+    Constant { interval: u32 },
+    /// Exponentially distributed gaps averaging `rate` messages per tick,
+    /// drawn from a generator seeded with `seed`, or from the flow's own
+    /// stream of the simulation seed if it has none.
+    Poisson { rate: f64, seed: Option<u64> },
 }
 
-#[derive(Debug)]
+/// Sends `count` messages between the same pair of nodes.
+///
+/// Every generated message is a copy of `template`, with message IDs
+/// counting up from the template's ID.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowInstr {
+    template: MsgInstr,
+    arrivals: Arrivals,
+    count: u32,
+}
+
+/// Floods a message from `start_node` to every node in the network.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BcastInstr {
+    timestamp: u32,
+    msg_id: u32,
+    start_node: u32,
+    payload: Payload,
+}
+
+/// Sends a copy of a message from `start_node` to each of `end_nodes`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct McastInstr {
+    timestamp: u32,
+    msg_id: u32,
+    start_node: u32,
+    end_nodes: Vec<u32>,
+    payload: Payload,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct RepInstr {
     timestamp: u32,
     msg_id: Option<u32>,
 }
 
-#[derive(Debug)]
+/// Saves the whole simulation state to `filename` once every instruction
+/// of its tick has run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotInstr {
+    timestamp: u32,
+    filename: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct EndInstr(u32);
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Instr {
     Msg(MsgInstr),
+    Bcast(BcastInstr),
+    Mcast(McastInstr),
+    Flow(FlowInstr),
     Rep(RepInstr),
+    Snapshot(SnapshotInstr),
     EndSim(EndInstr),
 }
 
@@ -26,18 +179,63 @@ impl Instr {
     pub fn timestamp(&self) -> u32 {
         match self {
             Instr::Msg(m) => m.timestamp(),
+            Instr::Bcast(b) => b.timestamp(),
+            Instr::Mcast(m) => m.timestamp(),
+            Instr::Flow(f) => f.timestamp(),
             Instr::Rep(r) => r.timestamp(),
+            Instr::Snapshot(s) => s.timestamp,
             Instr::EndSim(e) => e.timestamp(),
         }
     }
 
+    /// Name of the instruction as written in the sim file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Instr::Msg(_) => "msg",
+            Instr::Bcast(_) => "bcast",
+            Instr::Mcast(_) => "mcast",
+            Instr::Flow(f) => match f.arrivals() {
+                Arrivals::Constant { .. } => "flow",
+                Arrivals::Poisson { .. } => "poisson",
+            },
+            Instr::Rep(_) => "rep",
+            Instr::Snapshot(_) => "snapshot",
+            Instr::EndSim(_) => "endSim",
+        }
+    }
+
+    /// ID of the message the instruction sends or reports on, if any.
+    pub fn msg_id(&self) -> Option<u32> {
+        match self {
+            Instr::Msg(m) => Some(m.msg_id()),
+            Instr::Bcast(b) => Some(b.msg_id()),
+            Instr::Mcast(m) => Some(m.msg_id()),
+            Instr::Flow(f) => Some(f.msg_id()),
+            Instr::Rep(r) => r.msg_id(),
+            Instr::Snapshot(_) | Instr::EndSim(_) => None,
+        }
+    }
+
     pub fn is_endsim(&self) -> bool {
         match self {
             Instr::Msg(m) => m.is_endsim(),
+            Instr::Bcast(_) | Instr::Mcast(_) | Instr::Flow(_) | Instr::Snapshot(_) => false,
             Instr::Rep(r) => r.is_endsim(),
             Instr::EndSim(e) => e.is_endsim(),
         }
     }
+
+    /// Applies a trailing `key=value` option, as the instruction's line in
+    /// a sim file would take it.
+    pub fn set_option(&mut self, key: &str, value: &str) -> Result<(), SimErrCode> {
+        match self {
+            Instr::Msg(m) => m.set_option(key, value),
+            Instr::Bcast(b) => b.set_option(key, value),
+            Instr::Mcast(m) => m.set_option(key, value),
+            Instr::Flow(f) => f.set_option(key, value),
+            Instr::Rep(_) | Instr::Snapshot(_) | Instr::EndSim(_) => Err(SimErrCode::BadOption),
+        }
+    }
 }
 
 impl MsgInstr {
@@ -47,8 +245,49 @@ impl MsgInstr {
             msg_id,
             start_node,
             end_node,
+            payload: Payload::default(),
+            reliability: None,
         }
     }
+
+    /// Applies a single `key=value` option trailing a `msg` instruction.
+    ///
+    /// Besides the `size=` and `data=` payload options, `reliable` asks for
+    /// the message to be acknowledged and resent until it is, and
+    /// `rto=<ticks>` and `retries=<count>` tune how (either one also turns
+    /// on reliable mode).
+    pub fn set_option(&mut self, key: &str, value: &str) -> Result<(), SimErrCode> {
+        if self.payload.set_option(key, value)? {
+            return Ok(());
+        }
+        match key {
+            "reliable" => match value {
+                "" | "true" => {
+                    self.reliability.get_or_insert_with(Reliability::default);
+                }
+                "false" => self.reliability = None,
+                _ => return Err(SimErrCode::BadOption),
+            },
+            "rto" => match value.parse::<u32>() {
+                Ok(rto) if rto > 0 => {
+                    self.reliability
+                        .get_or_insert_with(Reliability::default)
+                        .rto = rto
+                }
+                _ => return Err(SimErrCode::BadOption),
+            },
+            "retries" => match value.parse::<u32>() {
+                Ok(retries) => {
+                    self.reliability
+                        .get_or_insert_with(Reliability::default)
+                        .retries = retries
+                }
+                Err(_) => return Err(SimErrCode::BadOption),
+            },
+            _ => return Err(SimErrCode::BadOption),
+        }
+        Ok(())
+    }
     pub fn timestamp(&self) -> u32 {
         self.timestamp
     }
@@ -61,11 +300,140 @@ impl MsgInstr {
     pub fn msg_id(&self) -> u32 {
         self.msg_id
     }
+    pub fn payload(&self) -> &Payload {
+        &self.payload
+    }
+    pub fn reliability(&self) -> Option<Reliability> {
+        self.reliability
+    }
+    /// Payload and reliability options that were set, as `key=value`
+    /// pairs in the order an instruction line would list them.
+    pub fn options(&self) -> Vec<String> {
+        let mut options = self.payload.options();
+        if let Some(reliability) = self.reliability {
+            options.push("reliable".to_string());
+            if reliability.rto != DEFAULT_RTO {
+                options.push(format!("rto={}", reliability.rto));
+            }
+            if reliability.retries != DEFAULT_RETRIES {
+                options.push(format!("retries={}", reliability.retries));
+            }
+        }
+        options
+    }
     fn is_endsim(&self) -> bool {
         false
     }
 }
 
+impl FlowInstr {
+    pub fn new(template: MsgInstr, arrivals: Arrivals, count: u32) -> FlowInstr {
+        FlowInstr {
+            template,
+            arrivals,
+            count,
+        }
+    }
+    /// Trailing options are the same as for a single `msg` instruction.
+    pub fn set_option(&mut self, key: &str, value: &str) -> Result<(), SimErrCode> {
+        self.template.set_option(key, value)
+    }
+    pub fn timestamp(&self) -> u32 {
+        self.template.timestamp()
+    }
+    pub fn msg_id(&self) -> u32 {
+        self.template.msg_id()
+    }
+    /// The message every generated message is a copy of.
+    pub fn template(&self) -> &MsgInstr {
+        &self.template
+    }
+    pub fn arrivals(&self) -> Arrivals {
+        self.arrivals
+    }
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+    /// ID of the flow's last message, or `None` if its IDs would run past
+    /// the largest one. A flow of no messages ends where it starts.
+    pub fn last_id(&self) -> Option<u32> {
+        self.msg_id().checked_add(self.count.saturating_sub(1))
+    }
+    /// Builds the `index`th message of the flow, sent at `timestamp`, or
+    /// `None` if its ID would run past the largest one.
+    pub fn message(&self, timestamp: u32, index: u32) -> Option<MsgInstr> {
+        Some(MsgInstr {
+            timestamp,
+            msg_id: self.template.msg_id().checked_add(index)?,
+            ..self.template.clone()
+        })
+    }
+}
+
+impl BcastInstr {
+    pub fn new(timestamp: u32, msg_id: u32, start_node: u32) -> BcastInstr {
+        BcastInstr {
+            timestamp,
+            msg_id,
+            start_node,
+            payload: Payload::default(),
+        }
+    }
+    /// Broadcasts only take the `size=` and `data=` payload options.
+    pub fn set_option(&mut self, key: &str, value: &str) -> Result<(), SimErrCode> {
+        match self.payload.set_option(key, value)? {
+            true => Ok(()),
+            false => Err(SimErrCode::BadOption),
+        }
+    }
+    pub fn timestamp(&self) -> u32 {
+        self.timestamp
+    }
+    pub fn start_node(&self) -> u32 {
+        self.start_node
+    }
+    pub fn msg_id(&self) -> u32 {
+        self.msg_id
+    }
+    pub fn payload(&self) -> &Payload {
+        &self.payload
+    }
+}
+
+impl McastInstr {
+    pub fn new(timestamp: u32, msg_id: u32, start_node: u32, end_nodes: Vec<u32>) -> McastInstr {
+        McastInstr {
+            timestamp,
+            msg_id,
+            start_node,
+            end_nodes,
+            payload: Payload::default(),
+        }
+    }
+    /// Multicasts only take the `size=` and `data=` payload options.
+    pub fn set_option(&mut self, key: &str, value: &str) -> Result<(), SimErrCode> {
+        match self.payload.set_option(key, value)? {
+            true => Ok(()),
+            false => Err(SimErrCode::BadOption),
+        }
+    }
+    pub fn timestamp(&self) -> u32 {
+        self.timestamp
+    }
+    pub fn start_node(&self) -> u32 {
+        self.start_node
+    }
+    pub fn end_nodes(&self) -> &[u32] {
+        &self.end_nodes
+    }
+    pub fn msg_id(&self) -> u32 {
+        self.msg_id
+    }
+    pub fn payload(&self) -> &Payload {
+        &self.payload
+    }
+}
+
 impl RepInstr {
     pub fn new(timestamp: u32, msg_id: Option<u32>) -> RepInstr {
         RepInstr { timestamp, msg_id }
@@ -81,6 +449,18 @@ impl RepInstr {
     }
 }
 
+impl SnapshotInstr {
+    pub fn new(timestamp: u32, filename: String) -> SnapshotInstr {
+        SnapshotInstr {
+            timestamp,
+            filename,
+        }
+    }
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+}
+
 impl EndInstr {
     pub fn new(timestamp: u32) -> EndInstr {
         EndInstr(timestamp)
@@ -92,3 +472,67 @@ impl EndInstr {
         true
     }
 }
+
+impl Snap for MsgInstr {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.val(self.timestamp)?;
+        out.val(self.msg_id)?;
+        out.val(self.start_node)?;
+        out.val(self.end_node)?;
+        out.opt(self.payload.size)?;
+        out.bytes(self.payload.data())?;
+        out.opt(self.reliability.map(|r| r.rto))?;
+        if let Some(reliability) = self.reliability {
+            out.val(reliability.retries)?;
+        }
+        Ok(())
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        let mut msg = MsgInstr::new(inp.val()?, inp.val()?, inp.val()?, inp.val()?);
+        msg.payload = Payload {
+            size: inp.opt()?,
+            data: inp.bytes()?,
+        };
+        if let Some(rto) = inp.opt()? {
+            msg.reliability = Some(Reliability {
+                rto,
+                retries: inp.val()?,
+            });
+        }
+        Ok(msg)
+    }
+}
+
+impl Snap for FlowInstr {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        self.template.save(out)?;
+        match self.arrivals {
+            Arrivals::Constant { interval } => {
+                out.val("constant")?;
+                out.val(interval)?;
+            }
+            Arrivals::Poisson { rate, seed } => {
+                out.val("poisson")?;
+                out.val(rate)?;
+                out.opt(seed)?;
+            }
+        }
+        out.val(self.count)
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        let template = MsgInstr::load(inp)?;
+        let arrivals = match inp.word()?.as_str() {
+            "constant" => Arrivals::Constant {
+                interval: inp.val()?,
+            },
+            "poisson" => Arrivals::Poisson {
+                rate: inp.val()?,
+                seed: inp.opt()?,
+            },
+            _ => return Err(SnapErrCode::BadToken),
+        };
+        Ok(FlowInstr::new(template, arrivals, inp.val()?))
+    }
+}
@@ -0,0 +1,224 @@
+use std::{fmt::Display, str::FromStr};
+
+use super::{builder::SimBuilder, Sim};
+use crate::{
+    timeline::{
+        ntwk::Ntwk,
+        utils::{
+            internal_utils::split_attr,
+            rng::{Rng, Stream},
+        },
+    },
+    ErrCode,
+};
+
+/// Most messages a workload may send on average, so a typo in the rate or
+/// duration can't leave generation running for hours.
+const MAX_MESSAGES: f64 = 1_000_000.0;
+
+/// When the messages of a [`Workload`] are sent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArrivalProcess {
+    /// One message every `interval` ticks, from tick 0.
+    Periodic { interval: u32 },
+    /// Exponentially distributed gaps averaging `rate` messages per tick.
+    Poisson { rate: f64 },
+}
+
+/// Random traffic between the nodes of a network, for [`Sim::generate`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Workload {
+    pub arrivals: ArrivalProcess,
+    /// Tick the run ends at; messages are only sent before it.
+    pub duration: u32,
+    /// How much destinations favour a few hot nodes: the `k`th hottest is
+    /// picked with weight `1 / k^skew`, so 0 spreads messages evenly.
+    pub skew: f64,
+    /// Ticks between reports, or `None` for a single report at the end.
+    pub rep_every: Option<u32>,
+    /// Size in bytes of each message, or `None` for the default.
+    pub size: Option<u32>,
+}
+
+impl Workload {
+    /// Evenly spread messages with the given arrivals until `duration`,
+    /// reported on once at the end.
+    pub fn new(arrivals: ArrivalProcess, duration: u32) -> Workload {
+        Workload {
+            arrivals,
+            duration,
+            skew: 0.0,
+            rep_every: None,
+            size: None,
+        }
+    }
+
+    fn check(&self) -> Result<(), &'static str> {
+        match self.arrivals {
+            ArrivalProcess::Periodic { interval: 0 } => return Err("the interval must be above 0"),
+            ArrivalProcess::Poisson { rate } if !(rate > 0.0 && rate.is_finite()) => {
+                return Err("the rate must be above 0")
+            }
+            _ => (),
+        }
+        if self.duration == 0 {
+            return Err("the duration must be above 0");
+        }
+        let messages = match self.arrivals {
+            ArrivalProcess::Periodic { interval } => self.duration.div_ceil(interval) as f64,
+            ArrivalProcess::Poisson { rate } => rate * self.duration as f64,
+        };
+        if messages > MAX_MESSAGES {
+            return Err("it would send more than a million messages");
+        }
+        if !(self.skew >= 0.0 && self.skew.is_finite()) {
+            return Err("the skew must be 0 or more");
+        }
+        if self.rep_every == Some(0) {
+            return Err("reports must be at least a tick apart");
+        }
+        Ok(())
+    }
+
+    /// Ticks messages are sent at, in order.
+    fn send_times(&self, rng: &mut Rng) -> Vec<u32> {
+        match self.arrivals {
+            ArrivalProcess::Periodic { interval } => {
+                (0..self.duration).step_by(interval as usize).collect()
+            }
+            ArrivalProcess::Poisson { rate } => {
+                let mut times = Vec::new();
+                let mut time = rng.next_exp(rate);
+                while time < self.duration as f64 {
+                    times.push(time as u32);
+                    time += rng.next_exp(rate);
+                }
+                times
+            }
+        }
+    }
+}
+
+impl Sim {
+    /// Builds a sim of `msg` instructions between random pairs of the
+    /// nodes in `ntwk`, followed by its `rep` instructions and `endSim`.
+    ///
+    /// Pairs and send times are drawn from `seed`, which the sim also
+    /// keeps as its own seed, so the same arguments always give the same
+    /// run.
+    ///
+    /// ```
+    /// # use guwah::{ArrivalProcess, Ntwk, QueueDelays, Sim, Simulator, Topology, Workload};
+    /// let ntwk = Ntwk::generate(Topology::Star { nodes: 5 }, QueueDelays::default(), 1)?;
+    /// let workload = Workload::new(ArrivalProcess::Periodic { interval: 10 }, 100);
+    /// let sim = Sim::generate(&workload, &ntwk, 1)?;
+    /// let result = Simulator::new(ntwk, sim).run()?;
+    /// assert_eq!(result.reports().len(), 10);
+    /// # Ok::<(), guwah::ErrCode>(())
+    /// ```
+    pub fn generate(workload: &Workload, ntwk: &Ntwk, seed: u64) -> Result<Sim, ErrCode> {
+        let bad = |problem: &str| ErrCode::BadScenario(format!("workload {workload}: {problem}"));
+        workload.check().map_err(bad)?;
+        let mut nodes: Vec<u32> = ntwk.node_ids().collect();
+        if nodes.len() < 2 {
+            return Err(bad("the network needs at least 2 nodes"));
+        }
+
+        let mut rng = Rng::stream(seed, Stream::Workload);
+        // Which nodes are hot is random too, hottest first
+        for idx in (1..nodes.len()).rev() {
+            nodes.swap(idx, rng.next_below_or_eq(idx as u32) as usize);
+        }
+        let mut total = 0.0;
+        let cumulative: Vec<f64> = (1..=nodes.len())
+            .map(|rank| {
+                total += 1.0 / (rank as f64).powf(workload.skew);
+                total
+            })
+            .collect();
+
+        let mut builder = SimBuilder::new();
+        builder.seed(seed);
+        for (id, time) in (1..).zip(workload.send_times(&mut rng)) {
+            let pick = rng.next_f64() * total;
+            let dst_idx = cumulative
+                .partition_point(|&sum| sum <= pick)
+                .min(nodes.len() - 1);
+            // Any node but the destination
+            let src_idx = rng.next_below_or_eq(nodes.len() as u32 - 2) as usize;
+            let src_idx = if src_idx >= dst_idx {
+                src_idx + 1
+            } else {
+                src_idx
+            };
+            builder.msg(time, id, nodes[src_idx], nodes[dst_idx]);
+            if let Some(size) = workload.size {
+                builder.opt("size", &size.to_string());
+            }
+        }
+        if let Some(every) = workload.rep_every {
+            for time in (every..workload.duration).step_by(every as usize) {
+                builder.rep(time, None);
+            }
+        }
+        builder.rep(workload.duration, None).end(workload.duration);
+        builder.build()
+    }
+}
+
+impl Display for Workload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.arrivals {
+            ArrivalProcess::Periodic { interval } => write!(f, "periodic:{interval}")?,
+            ArrivalProcess::Poisson { rate } => write!(f, "poisson:{rate}")?,
+        }
+        write!(f, ":{}", self.duration)?;
+        if self.skew != 0.0 {
+            write!(f, ",skew={}", self.skew)?;
+        }
+        if let Some(every) = self.rep_every {
+            write!(f, ",rep={every}")?;
+        }
+        if let Some(size) = self.size {
+            write!(f, ",size={size}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Workload {
+    type Err = ErrCode;
+
+    /// Parses the form [`Display`] writes: `periodic:INTERVAL:DURATION` or
+    /// `poisson:RATE:DURATION`, then any of `skew=`, `rep=` and `size=`,
+    /// e.g. `poisson:0.5:1000,skew=1.2,rep=100`.
+    fn from_str(s: &str) -> Result<Workload, ErrCode> {
+        let bad = || ErrCode::BadScenario(format!("bad workload {s}"));
+        let mut tokens = s.split(',');
+        let arrivals = tokens.next().unwrap_or("");
+        let mut workload = match arrivals.split(':').collect::<Vec<_>>().as_slice() {
+            ["periodic", interval, duration] => match (interval.parse(), duration.parse()) {
+                (Ok(interval), Ok(duration)) => {
+                    Workload::new(ArrivalProcess::Periodic { interval }, duration)
+                }
+                _ => return Err(bad()),
+            },
+            ["poisson", rate, duration] => match (rate.parse(), duration.parse()) {
+                (Ok(rate), Ok(duration)) => {
+                    Workload::new(ArrivalProcess::Poisson { rate }, duration)
+                }
+                _ => return Err(bad()),
+            },
+            _ => return Err(bad()),
+        };
+        for tok in tokens {
+            match split_attr(tok) {
+                ("skew", skew) => workload.skew = skew.parse().map_err(|_| bad())?,
+                ("rep", every) => workload.rep_every = Some(every.parse().map_err(|_| bad())?),
+                ("size", size) => workload.size = Some(size.parse().map_err(|_| bad())?),
+                _ => return Err(bad()),
+            }
+        }
+        Ok(workload)
+    }
+}
@@ -0,0 +1,101 @@
+use std::io::{self, Write};
+
+use super::{
+    instr::{Arrivals, Instr, Payload},
+    Sim,
+};
+
+impl Sim {
+    /// Writes the sim in the `.sim` format, one instruction per line in
+    /// the order they run, which reads back as an equal sim.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] on a payload or snapshot
+    /// filename an instruction line can't hold: one that isn't UTF-8, or
+    /// has a comma, `#`, line break, or leading or trailing whitespace.
+    pub fn write_sim<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        if let Some(seed) = self.seed {
+            writeln!(out, "0,seed,{seed}")?;
+        }
+        for instr in self.instr_set_set.iter().flat_map(|set| set.iter()) {
+            writeln!(out, "{}", instr_line(instr)?)?;
+        }
+        Ok(())
+    }
+}
+
+fn instr_line(instr: &Instr) -> io::Result<String> {
+    let mut fields = vec![instr.timestamp().to_string(), instr.name().to_string()];
+    let (args, options): (Vec<String>, Vec<String>) = match instr {
+        Instr::Msg(m) => {
+            check_payload(m.payload())?;
+            (
+                ids(&[m.msg_id(), m.start_node(), m.end_node()]),
+                m.options(),
+            )
+        }
+        Instr::Bcast(b) => {
+            check_payload(b.payload())?;
+            (ids(&[b.msg_id(), b.start_node()]), b.payload().options())
+        }
+        Instr::Mcast(m) => {
+            check_payload(m.payload())?;
+            let mut args = ids(&[m.msg_id(), m.start_node()]);
+            args.extend(ids(m.end_nodes()));
+            (args, m.payload().options())
+        }
+        Instr::Flow(f) => {
+            let template = f.template();
+            check_payload(template.payload())?;
+            let mut args = ids(&[f.msg_id(), template.start_node(), template.end_node()]);
+            match f.arrivals() {
+                Arrivals::Constant { interval } => args.push(interval.to_string()),
+                Arrivals::Poisson { rate, .. } => args.push(rate.to_string()),
+            }
+            args.push(f.count().to_string());
+            if let Arrivals::Poisson {
+                seed: Some(seed), ..
+            } = f.arrivals()
+            {
+                args.push(seed.to_string());
+            }
+            (args, template.options())
+        }
+        Instr::Rep(r) => (
+            r.msg_id().map(|id| ids(&[id])).unwrap_or_default(),
+            Vec::new(),
+        ),
+        Instr::Snapshot(s) => {
+            check_text(s.filename().as_bytes(), "snapshot filename")?;
+            (vec![s.filename().to_string()], Vec::new())
+        }
+        Instr::EndSim(_) => (Vec::new(), Vec::new()),
+    };
+    fields.extend(args);
+    fields.extend(options);
+    Ok(fields.join(","))
+}
+
+fn ids(ids: &[u32]) -> Vec<String> {
+    ids.iter().map(u32::to_string).collect()
+}
+
+fn check_payload(payload: &Payload) -> io::Result<()> {
+    match payload.data() {
+        Some(data) => check_text(data, "payload"),
+        None => Ok(()),
+    }
+}
+
+/// Checks that `text` reads back the same from a field of an instruction
+/// line.
+fn check_text(text: &[u8], what: &str) -> io::Result<()> {
+    let writable = std::str::from_utf8(text)
+        .is_ok_and(|text| text.trim() == text && !text.contains([',', '#', '\n', '\r']));
+    match writable {
+        true => Ok(()),
+        false => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{what} can't be written to a sim file"),
+        )),
+    }
+}
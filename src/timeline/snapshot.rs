@@ -0,0 +1,228 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    str::FromStr,
+};
+
+use crate::timeline::{engine::Engine, ntwk::Ntwk};
+
+/// First line of every snapshot file, with the format version.
+const SNAPSHOT_HEADER: &str = "guwah-snapshot 3";
+
+#[derive(Debug, PartialEq)]
+pub enum SnapErrCode {
+    /// The file couldn't be read, with why.
+    BadFile(String),
+    BadHeader,
+    UnexpectedEnd,
+    BadToken,
+}
+
+impl Display for SnapErrCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            SnapErrCode::BadFile(e) => return write!(f, "file can't be read: {e}"),
+            SnapErrCode::BadHeader => "not a snapshot, or from another version",
+            SnapErrCode::UnexpectedEnd => "file ends early",
+            SnapErrCode::BadToken => "unexpected contents",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+/// A piece of simulation state that can be written to a snapshot and read
+/// back exactly as it was.
+pub trait Snap: Sized {
+    fn save<W: Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()>;
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode>;
+}
+
+impl Snap for u32 {
+    fn save<W: Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.val(self)
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        inp.val()
+    }
+}
+
+impl Snap for (u32, u32) {
+    fn save<W: Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.val(self.0)?;
+        out.val(self.1)
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        Ok((inp.val()?, inp.val()?))
+    }
+}
+
+/// Writes snapshot values as whitespace separated tokens.
+///
+/// Each `tag` starts a new line, which only makes the file easier to read;
+/// the reader doesn't care where lines break.
+pub struct SnapWriter<W: Write> {
+    out: W,
+    line_empty: bool,
+}
+
+impl<W: Write> SnapWriter<W> {
+    pub fn new(out: W) -> SnapWriter<W> {
+        SnapWriter {
+            out,
+            line_empty: true,
+        }
+    }
+
+    /// Starts a new line with `tag`.
+    pub fn tag(&mut self, tag: &str) -> io::Result<()> {
+        if !self.line_empty {
+            writeln!(self.out)?;
+        }
+        write!(self.out, "{tag}")?;
+        self.line_empty = false;
+        Ok(())
+    }
+
+    pub fn val<T: Display>(&mut self, value: T) -> io::Result<()> {
+        match self.line_empty {
+            true => write!(self.out, "{value}")?,
+            false => write!(self.out, " {value}")?,
+        }
+        self.line_empty = false;
+        Ok(())
+    }
+
+    /// Writes `-` for `None`.
+    pub fn opt<T: Display>(&mut self, value: Option<T>) -> io::Result<()> {
+        match value {
+            Some(value) => self.val(value),
+            None => self.val("-"),
+        }
+    }
+
+    /// Writes bytes as hex, or `-` for `None`.
+    pub fn bytes(&mut self, value: Option<&[u8]>) -> io::Result<()> {
+        match value {
+            // Empty payloads still need a token of their own
+            Some([]) => self.val("x"),
+            Some(bytes) => {
+                let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+                self.val(hex)
+            }
+            None => self.val("-"),
+        }
+    }
+
+    /// Writes the length of `items`, then each item.
+    pub fn list<'a, T, I>(&mut self, items: I) -> io::Result<()>
+    where
+        T: Snap + 'a,
+        I: ExactSizeIterator<Item = &'a T>,
+    {
+        self.val(items.len())?;
+        for item in items {
+            item.save(self)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        writeln!(self.out)?;
+        self.out.flush()
+    }
+}
+
+/// Reads back the tokens written by a [`SnapWriter`].
+#[derive(Clone)]
+pub struct SnapReader {
+    tokens: std::vec::IntoIter<String>,
+}
+
+impl SnapReader {
+    pub fn from_file(filename: &str) -> Result<SnapReader, SnapErrCode> {
+        let mut contents = String::new();
+        File::open(filename)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| SnapErrCode::BadFile(e.to_string()))?;
+
+        let contents = contents
+            .strip_prefix(SNAPSHOT_HEADER)
+            .ok_or(SnapErrCode::BadHeader)?;
+        let tokens: Vec<String> = contents.split_whitespace().map(String::from).collect();
+        Ok(SnapReader {
+            tokens: tokens.into_iter(),
+        })
+    }
+
+    fn next(&mut self) -> Result<String, SnapErrCode> {
+        self.tokens.next().ok_or(SnapErrCode::UnexpectedEnd)
+    }
+
+    /// Checks that the next token is `tag`.
+    pub fn tag(&mut self, tag: &str) -> Result<(), SnapErrCode> {
+        match self.next()? == tag {
+            true => Ok(()),
+            false => Err(SnapErrCode::BadToken),
+        }
+    }
+
+    /// Reads one of several tags, for values that come in different kinds.
+    pub fn word(&mut self) -> Result<String, SnapErrCode> {
+        self.next()
+    }
+
+    pub fn val<T: FromStr>(&mut self) -> Result<T, SnapErrCode> {
+        self.next()?.parse().map_err(|_| SnapErrCode::BadToken)
+    }
+
+    pub fn opt<T: FromStr>(&mut self) -> Result<Option<T>, SnapErrCode> {
+        match self.next()?.as_str() {
+            "-" => Ok(None),
+            tok => tok.parse().map(Some).map_err(|_| SnapErrCode::BadToken),
+        }
+    }
+
+    pub fn bytes(&mut self) -> Result<Option<Vec<u8>>, SnapErrCode> {
+        let tok = self.next()?;
+        match tok.as_str() {
+            "-" => Ok(None),
+            "x" => Ok(Some(Vec::new())),
+            hex if hex.is_ascii() && hex.len() % 2 == 0 => (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+                .collect::<Result<Vec<u8>, _>>()
+                .map(Some)
+                .map_err(|_| SnapErrCode::BadToken),
+            _ => Err(SnapErrCode::BadToken),
+        }
+    }
+
+    /// Reads a length, then that many items.
+    pub fn list<T: Snap>(&mut self) -> Result<Vec<T>, SnapErrCode> {
+        let len: usize = self.val()?;
+        (0..len).map(|_| T::load(self)).collect()
+    }
+}
+
+/// Writes the state of a run, as of the end of `tick`, to `filename`.
+pub fn save(filename: &str, tick: u32, ntwk: &Ntwk, engine: &Engine) -> io::Result<()> {
+    let mut out = SnapWriter::new(BufWriter::new(File::create(filename)?));
+    out.tag(SNAPSHOT_HEADER)?;
+    out.tag("tick")?;
+    out.val(tick)?;
+    ntwk.save(&mut out)?;
+    engine.save(&mut out)?;
+    out.finish()
+}
+
+/// Opens a snapshot, returning the tick it was taken at and a reader
+/// positioned at the network, which is followed by the engine.
+pub fn open(filename: &str) -> Result<(u32, SnapReader), SnapErrCode> {
+    let mut inp = SnapReader::from_file(filename)?;
+    inp.tag("tick")?;
+    let tick = inp.val()?;
+    Ok((tick, inp))
+}
@@ -0,0 +1,211 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use crate::timeline::msg::{packet::Packet, report::DropReason};
+
+/// Which kind of packet a trace record is about.
+#[derive(Debug, Clone, Copy)]
+pub enum PacketKind {
+    Msg,
+    Fragment,
+    Bcast,
+    Ack,
+}
+
+impl PacketKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PacketKind::Msg => "msg",
+            PacketKind::Fragment => "fragment",
+            PacketKind::Bcast => "bcast",
+            PacketKind::Ack => "ack",
+        }
+    }
+}
+
+/// The message ID and kind of a packet, as written to the trace.
+///
+/// ACKs carry the ID of the message they acknowledge.
+#[derive(Debug, Clone, Copy)]
+pub struct PacketTag {
+    id: u32,
+    kind: PacketKind,
+}
+
+impl PacketTag {
+    pub fn msg(id: u32) -> PacketTag {
+        PacketTag {
+            id,
+            kind: PacketKind::Msg,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl From<&Packet> for PacketTag {
+    fn from(value: &Packet) -> Self {
+        match value {
+            Packet::Message(m) => PacketTag {
+                id: m.id(),
+                kind: match (m.is_broadcast(), m.frag()) {
+                    (true, _) => PacketKind::Bcast,
+                    (false, Some(_)) => PacketKind::Fragment,
+                    (false, None) => PacketKind::Msg,
+                },
+            },
+            Packet::Ack(a) => PacketTag {
+                id: a.msg_id(),
+                kind: PacketKind::Ack,
+            },
+        }
+    }
+}
+
+// Written as e.g. `msg 3` or `ack 3`, for the verbose tick table
+impl Display for PacketTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.kind.as_str(), self.id)
+    }
+}
+
+/// Something that happened during the simulation, worth a trace record.
+#[derive(Debug, Clone, Copy)]
+pub enum TraceEvent {
+    /// An instruction from the sim file fired.
+    Instr { name: &'static str, id: Option<u32> },
+    /// A packet arrived at a node, whether it stays there or not.
+    Receive { node: u32, packet: PacketTag },
+    /// A packet joined a node's queue to wait for service.
+    Enqueue { node: u32, packet: PacketTag },
+    /// A packet was sent from `node` across the link to `to`, recorded at
+    /// the tick it finished going out.
+    Transmit {
+        node: u32,
+        to: u32,
+        packet: PacketTag,
+    },
+    Drop {
+        node: u32,
+        packet: PacketTag,
+        reason: DropReason,
+    },
+    /// A message reached its destination `node`; `partial` if some of its
+    /// fragments never made it.
+    Deliver {
+        node: u32,
+        packet: PacketTag,
+        partial: bool,
+    },
+}
+
+/// Optional sink that writes every simulation event as a line of JSON.
+///
+/// Each record is one object with at least `tick` and `event` fields,
+/// followed by `node`, `id` and `kind` for events about packets, e.g.
+///
+/// ```text
+/// {"tick":7,"event":"transmit","node":4,"to":1,"id":1,"kind":"msg"}
+/// ```
+///
+/// A trace that can't be written to any more is reported once and then
+/// switched off, so the simulation itself carries on.
+pub struct Trace {
+    out: Option<BufWriter<File>>,
+}
+
+impl Trace {
+    /// A trace that throws every record away.
+    pub fn none() -> Trace {
+        Trace { out: None }
+    }
+
+    /// A trace written to the file `filename`, replacing its contents.
+    pub fn to_file(filename: &str) -> io::Result<Trace> {
+        let file = File::create(filename)?;
+        Ok(Trace {
+            out: Some(BufWriter::new(file)),
+        })
+    }
+
+    pub fn record(&mut self, tick: u32, event: TraceEvent) {
+        let out = match self.out.as_mut() {
+            Some(out) => out,
+            None => return,
+        };
+        let written = write_record(out, tick, event).and_then(|_| writeln!(out));
+        if let Err(e) = written {
+            eprintln!("trace write failed, tracing stopped: {e}");
+            self.out = None;
+        }
+    }
+}
+
+impl Drop for Trace {
+    fn drop(&mut self) {
+        if let Some(out) = self.out.as_mut() {
+            if let Err(e) = out.flush() {
+                eprintln!("trace write failed: {e}");
+            }
+        }
+    }
+}
+
+fn write_record<W: Write>(out: &mut W, tick: u32, event: TraceEvent) -> io::Result<()> {
+    write!(out, "{{\"tick\":{tick}")?;
+    match event {
+        TraceEvent::Instr { name, id } => {
+            write!(out, ",\"event\":\"instr\",\"instr\":\"{name}\"")?;
+            if let Some(id) = id {
+                write!(out, ",\"id\":{id}")?;
+            }
+        }
+        TraceEvent::Receive { node, packet } => {
+            write!(out, ",\"event\":\"receive\",\"node\":{node}")?;
+            write_packet(out, packet)?;
+        }
+        TraceEvent::Enqueue { node, packet } => {
+            write!(out, ",\"event\":\"enqueue\",\"node\":{node}")?;
+            write_packet(out, packet)?;
+        }
+        TraceEvent::Transmit { node, to, packet } => {
+            write!(out, ",\"event\":\"transmit\",\"node\":{node},\"to\":{to}")?;
+            write_packet(out, packet)?;
+        }
+        TraceEvent::Drop {
+            node,
+            packet,
+            reason,
+        } => {
+            write!(out, ",\"event\":\"drop\",\"node\":{node}")?;
+            write_packet(out, packet)?;
+            write!(out, ",\"reason\":\"{reason}\"")?;
+        }
+        TraceEvent::Deliver {
+            node,
+            packet,
+            partial,
+        } => {
+            write!(out, ",\"event\":\"deliver\",\"node\":{node}")?;
+            write_packet(out, packet)?;
+            if partial {
+                write!(out, ",\"partial\":true")?;
+            }
+        }
+    }
+    write!(out, "}}")
+}
+
+fn write_packet<W: Write>(out: &mut W, packet: PacketTag) -> io::Result<()> {
+    write!(
+        out,
+        ",\"id\":{},\"kind\":\"{}\"",
+        packet.id,
+        packet.kind.as_str()
+    )
+}
@@ -1 +1,2 @@
 pub(crate) mod internal_utils;
+pub(crate) mod rng;
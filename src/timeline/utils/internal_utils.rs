@@ -3,17 +3,38 @@ use std::{
     io::{BufRead, BufReader, Lines},
 };
 
-pub fn read_lines(filename: &String) -> Result<Lines<BufReader<File>>, String> {
+/// Stands in for a filename in errors about input that didn't come from a
+/// file.
+pub const READER_NAME: &str = "<input>";
+
+pub fn read_lines(filename: &str) -> Result<Lines<BufReader<File>>, String> {
     match File::open(filename) {
         Ok(file) => Ok(BufReader::new(file).lines()),
         Err(e) => Err(e.to_string()),
     }
 }
 
-pub fn strip_comment(line: String) -> String {
-    let comment = line.find('#');
-    match comment {
-        Some(loc) => line.split_at(loc).0.to_string(),
+/// Cuts the comment off a line of a `.ntwk` or `.sim` file and trims the
+/// whitespace around each of its comma-separated fields.
+///
+/// Handles tabs, a `\r` left over from CRLF line endings and a byte order
+/// mark, so an all-whitespace or all-comment line comes back empty.
+pub fn clean_line(line: &str) -> String {
+    let line = match line.find('#') {
+        Some(loc) => &line[..loc],
         None => line,
+    };
+    let line = line.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+    line.split(',').map(str::trim).collect::<Vec<_>>().join(",")
+}
+
+/// Splits a `key=value` token into its two halves.
+///
+/// A bare `key` with no `=` is returned with an empty value, which lets
+/// boolean options be written as just their name.
+pub fn split_attr(tok: &str) -> (&str, &str) {
+    match tok.split_once('=') {
+        Some((key, value)) => (key.trim(), value.trim()),
+        None => (tok.trim(), ""),
     }
 }
@@ -0,0 +1,102 @@
+use std::io;
+
+use crate::timeline::snapshot::{Snap, SnapErrCode, SnapReader, SnapWriter};
+
+/// Identifies one independent stream of random numbers.
+///
+/// Each stream is derived from the simulation seed and its own key, never
+/// from the draws of another stream, so e.g. adding a flow doesn't change
+/// the numbers any other flow sees.
+#[derive(Debug, Clone, Copy)]
+pub enum Stream {
+    Flow(u32),
+    /// Loss and jitter draws for the link `from -> to`.
+    Link(u32, u32),
+    /// Links of a generated network.
+    Topology,
+    /// Queue delays of a generated network's nodes.
+    QueueDelays,
+    /// Messages of a generated workload.
+    Workload,
+}
+
+/// Small, seedable pseudo-random number generator (SplitMix64).
+///
+/// Not suitable for anything security related, but fast and fully
+/// reproducible from its seed, which is all the simulator needs.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// Derives the generator for `stream` from the simulation seed.
+    pub fn stream(seed: u64, stream: Stream) -> Rng {
+        let (tag, a, b): (u64, u32, u32) = match stream {
+            Stream::Flow(id) => (1, id, 0),
+            Stream::Link(from, to) => (2, from, to),
+            Stream::Topology => (3, 0, 0),
+            Stream::QueueDelays => (4, 0, 0),
+            Stream::Workload => (5, 0, 0),
+        };
+        let mut state = seed;
+        for key in [tag, a as u64, b as u64] {
+            state = Rng::new(state ^ key).next_u64();
+        }
+        Rng::new(state)
+    }
+
+    /// Picks a seed for runs that weren't given one, from the system clock.
+    pub fn fresh_seed() -> u64 {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Rng::new(nanos).next_u64()
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed value in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        // Top 53 bits fill the mantissa exactly
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniformly distributed whole number in `0..=max`.
+    pub fn next_below_or_eq(&mut self, max: u32) -> u32 {
+        (self.next_f64() * (max as f64 + 1.0)) as u32
+    }
+
+    /// Standard normal value (mean 0, standard deviation 1).
+    pub fn next_normal(&mut self) -> f64 {
+        // Box-Muller transform, discarding the second value
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+    }
+
+    /// Exponentially distributed value with the given rate (mean `1 / rate`).
+    pub fn next_exp(&mut self, rate: f64) -> f64 {
+        -(1.0 - self.next_f64()).ln() / rate
+    }
+}
+
+impl Snap for Rng {
+    fn save<W: io::Write>(&self, out: &mut SnapWriter<W>) -> io::Result<()> {
+        out.val(self.state)
+    }
+
+    fn load(inp: &mut SnapReader) -> Result<Self, SnapErrCode> {
+        Ok(Rng::new(inp.val()?))
+    }
+}
@@ -0,0 +1,43 @@
+//! Problems `Sim::check` finds in a sim before it runs.
+
+use guwah::{Ntwk, Sim, SimProblem};
+
+fn ntwk() -> Ntwk {
+    "1,1,1\n2\nendNode\n2,1,1\n1\nendNode\nendNet"
+        .parse()
+        .unwrap()
+}
+
+fn problems(sim: &str) -> Vec<SimProblem> {
+    sim.parse::<Sim>().unwrap().check(&ntwk())
+}
+
+#[test]
+fn flow_over_a_numbered_message() {
+    assert_eq!(
+        problems("1,msg,12,1,2\n2,flow,10,1,2,1,5\n9,endSim"),
+        [SimProblem::FlowOverlap { flow: 10, msg: 12 }]
+    );
+    assert_eq!(
+        problems("1,flow,10,1,2,1,5\n2,bcast,14,1\n9,endSim"),
+        [SimProblem::FlowOverlap { flow: 10, msg: 14 }]
+    );
+}
+
+#[test]
+fn overlapping_flows() {
+    assert_eq!(
+        problems("1,flow,10,1,2,1,5\n2,poisson,13,2,1,0.5,100\n9,endSim"),
+        [SimProblem::FlowOverlap { flow: 13, msg: 13 }]
+    );
+    assert!(problems("1,flow,10,1,2,1,5\n2,flow,15,2,1,1,5\n19,endSim").is_empty());
+}
+
+#[test]
+fn rep_of_a_flow_message() {
+    assert!(problems("1,flow,10,1,2,2,5\n5,rep,12\n19,endSim").is_empty());
+    assert_eq!(
+        problems("1,flow,10,1,2,2,5\n4,rep,12\n19,endSim"),
+        [SimProblem::UnknownReport { msg: 12, tick: 4 }]
+    );
+}
@@ -0,0 +1,177 @@
+//! Runs the `guwah` binary the way a user would, feeding it stdin.
+
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+fn config(name: &str) -> String {
+    format!("{}/config/{name}", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Writes `contents` to a file named `name` for a test to read, returning
+/// its path.
+fn scratch(name: &str, contents: &str) -> String {
+    let path = format!("{}/{name}", env!("CARGO_TARGET_TMPDIR"));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Nodes 1 - 2 - 3 in a line, with a message each way and a report at the
+/// end.
+fn line_files() -> (String, String) {
+    (
+        scratch(
+            "debug_line.ntwk",
+            "1,1,1\n2\nendNode\n2,2,1\n1\n3\nendNode\n3,1,1\n2\nendNode\nendNet\n",
+        ),
+        scratch(
+            "debug_line.sim",
+            "1,msg,1,1,3\n4,msg,2,3,1\n20,rep\n20,endSim\n",
+        ),
+    )
+}
+
+/// Runs `guwah` with `args`, writing `stdin` to it.
+fn guwah(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_guwah"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn debugger_cannot_share_stdin_with_a_file() {
+    let ntwk = config("basic.ntwk");
+    let sim = config("basic.sim");
+    for args in [
+        ["-i", "-n", "-", "-s", sim.as_str()],
+        ["-i", "-n", ntwk.as_str(), "-s", "-"],
+    ] {
+        let output = guwah(&args, "");
+        assert_eq!(output.status.code(), Some(7), "{args:?}");
+    }
+}
+
+#[test]
+fn debugger_takes_a_script_on_stdin() {
+    let (ntwk, sim) = line_files();
+    let script = "step 2\n\
+                  break msg 2\n\
+                  continue\n\
+                  show node 3\n\
+                  run-until 9\n\
+                  rep\n\
+                  bogus\n\
+                  quit\n";
+    let output = guwah(&["-i", "-n", &ntwk, "-s", &sim, "-r", "1"], script);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    for expected in [
+        "t=0> t=2> breakpoint on msg 2\n",
+        "t=2> breakpoint: msg 2 at t=4\n",
+        "t=4> node 3  depth 0  bytes 0  forwarded 0  drops 0\n",
+        // run-until stops early at the next move of msg 2
+        "t=4> breakpoint: msg 2 at t=5\n",
+        "msg 1 [1 -> 3] 64 bytes, injected at t=1: in transit 2 -> 3\n",
+        "msg 2 [3 -> 1] 64 bytes, injected at t=4: queued at node 3\n",
+        "t=5> unknown command: bogus (try help)\n",
+    ] {
+        assert!(stdout.contains(expected), "{expected:?} not in {stdout:?}");
+    }
+    // Quitting at t=5 skips the report at the end
+    assert!(!stdout.contains("report at t=20"));
+}
+
+#[test]
+fn debugger_runs_to_the_end_once_the_script_runs_out() {
+    let (ntwk, sim) = line_files();
+    let args = ["-n", ntwk.as_str(), "-s", sim.as_str(), "-r", "1"];
+    let plain = String::from_utf8(guwah(&args, "").stdout).unwrap();
+    assert!(plain.starts_with("report at t=20"));
+
+    let mut interactive_args = vec!["-i"];
+    interactive_args.extend(args);
+    let output = guwah(&interactive_args, "step 3\n");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("t=0> t=3> \n{plain}")
+    );
+}
+
+#[test]
+fn failing_to_write_output_is_an_error() {
+    // Writes to /dev/full always fail with "no space left"
+    let Ok(full) = fs::OpenOptions::new().write(true).open("/dev/full") else {
+        return;
+    };
+    let ntwk = config("basic.ntwk");
+    for args in [
+        vec!["-g", "ring:5"],
+        vec!["-n", ntwk.as_str(), "-a"],
+        vec!["-n", ntwk.as_str(), "-a", "-f", "json"],
+        vec!["-n", ntwk.as_str(), "-w", "periodic:5:50"],
+    ] {
+        let status = Command::new(env!("CARGO_BIN_EXE_guwah"))
+            .args(&args)
+            .stdout(full.try_clone().unwrap())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert_eq!(status.code(), Some(13), "{args:?}");
+    }
+}
+
+#[test]
+fn resumed_run_keeps_its_seed_and_routing() {
+    for args in [
+        ["-R", "run.snap", "-r", "9"],
+        ["-R", "run.snap", "-o", "delay"],
+    ] {
+        let output = guwah(&args, "");
+        assert_eq!(output.status.code(), Some(7), "{args:?}");
+    }
+}
+
+#[test]
+fn drawing_without_a_message_reads_only_the_network() {
+    let (ntwk, _) = line_files();
+    let dot = format!("{}/line.dot", env!("CARGO_TARGET_TMPDIR"));
+    let output = guwah(&["-n", &ntwk, "-s", "no-such.sim", "-d", &dot], "");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(fs::read_to_string(&dot)
+        .unwrap()
+        .starts_with("digraph ntwk {"));
+}
+
+#[test]
+fn trace_records_a_transmit_when_it_finishes() {
+    // Served at tick 2, then 8 bytes at 4 a tick take until tick 4
+    let ntwk = scratch(
+        "slow_link.ntwk",
+        "1,1,1\n2,bw=4\nendNode\n2,1,1\n1\nendNode\nendNet\n",
+    );
+    let sim = scratch("slow_link.sim", "1,msg,1,1,2,size=8\n10,endSim\n");
+    let trace = format!("{}/slow_link.jsonl", env!("CARGO_TARGET_TMPDIR"));
+    let output = guwah(&["-n", &ntwk, "-s", &sim, "-t", &trace], "");
+    assert!(output.status.success());
+    let trace = fs::read_to_string(&trace).unwrap();
+    assert!(
+        trace.contains("{\"tick\":4,\"event\":\"transmit\",\"node\":1,\"to\":2,"),
+        "{trace}"
+    );
+}
@@ -0,0 +1,299 @@
+//! Runs small scenarios through the engine and checks what the reports say
+//! happened to each message.
+
+use guwah::{AckOutcome, DropReason, MsgStatus, Ntwk, NtwkBuilder, RunResult, Sim, Simulator};
+
+/// Nodes 1 - 2 - 3 in a line, linked both ways, each with a queue delay
+/// of 1.
+fn line() -> Ntwk {
+    NtwkBuilder::new()
+        .node(1, 1)
+        .link(1, 2)
+        .node(2, 1)
+        .link(2, 1)
+        .link(2, 3)
+        .node(3, 1)
+        .link(3, 2)
+        .build()
+        .unwrap()
+}
+
+/// `line`, with an MTU of `mtu` on every link.
+fn line_with_mtu(mtu: u32) -> Ntwk {
+    let mtu = mtu.to_string();
+    NtwkBuilder::new()
+        .node(1, 1)
+        .link(1, 2)
+        .attr("mtu", &mtu)
+        .node(2, 1)
+        .link(2, 1)
+        .attr("mtu", &mtu)
+        .link(2, 3)
+        .attr("mtu", &mtu)
+        .node(3, 1)
+        .link(3, 2)
+        .attr("mtu", &mtu)
+        .build()
+        .unwrap()
+}
+
+fn delivered(status: MsgStatus) -> bool {
+    matches!(status, MsgStatus::Delivered { .. })
+}
+
+#[test]
+fn reused_ids_keep_a_report_each() {
+    let sim: Sim = "1,msg,6,1,3\n5,msg,6,3,1\n40,endSim".parse().unwrap();
+    let result = Simulator::new(line(), sim).seed(1).run().unwrap();
+
+    let reports = result.reports_for(6);
+    assert_eq!(reports.len(), 2);
+    assert_eq!((reports[0].nth(), reports[0].start_node()), (1, 1));
+    assert_eq!((reports[1].nth(), reports[1].start_node()), (2, 3));
+    for report in reports {
+        let (_, delivery) = report.deliveries().next().unwrap();
+        assert!(delivered(delivery.status()));
+    }
+    assert_eq!(result.report(6).unwrap().injected_at(), 1);
+}
+
+#[test]
+fn broadcasts_fragment_over_small_mtus() {
+    let mut builder = NtwkBuilder::new();
+    builder.node(1, 1).link(1, 2).attr("mtu", "16");
+    builder.node(2, 1).link(2, 1).link(2, 3).attr("mtu", "16");
+    builder.node(3, 1).link(3, 2);
+    let ntwk = builder.build().unwrap();
+    let sim: Sim = "1,bcast,1,1,size=64\n200,endSim".parse().unwrap();
+    let result = Simulator::new(ntwk, sim).seed(1).run().unwrap();
+
+    let report = result.report(1).unwrap();
+    assert_eq!(report.deliveries().count(), 2);
+    for (node, delivery) in report.deliveries() {
+        assert!(delivered(delivery.status()), "node {node}");
+        assert_eq!(delivery.fragments(), 4, "node {node}");
+    }
+}
+
+/// `line`, with an MTU of 16 on the links out of 1 and 2 towards 3, and
+/// room for only 20 bytes in node 2's queue.
+fn narrow_line() -> Ntwk {
+    let mut builder = NtwkBuilder::new();
+    builder.node(1, 1).link(1, 2).attr("mtu", "16");
+    builder.node(2, 1).queue_capacity(2, 20).link(2, 1);
+    builder.link(2, 3).attr("mtu", "16");
+    builder.node(3, 1).link(3, 2);
+    builder.build().unwrap()
+}
+
+#[test]
+fn fragments_are_reassembled_at_the_end_node() {
+    let sim: Sim = "1,msg,1,1,3,size=64,data=split into four\n100,endSim"
+        .parse()
+        .unwrap();
+    let result = Simulator::new(line_with_mtu(16), sim)
+        .seed(1)
+        .run()
+        .unwrap();
+
+    let (node, delivery) = result.report(1).unwrap().deliveries().next().unwrap();
+    assert_eq!(node, 3);
+    assert!(delivered(delivery.status()));
+    assert_eq!(delivery.fragments(), 4);
+    assert_eq!(delivery.payload(), Some(&b"split into four"[..]));
+}
+
+#[test]
+fn missing_fragments_time_out_as_a_partial_delivery() {
+    // Node 2's queue only fits one fragment at a time, and they arrive
+    // back to back, so every other one is dropped
+    let sim: Sim = "1,msg,1,1,3,size=64\n100,endSim".parse().unwrap();
+    let result = Simulator::new(narrow_line(), sim).seed(1).run().unwrap();
+
+    let (_, delivery) = result.report(1).unwrap().deliveries().next().unwrap();
+    assert!(matches!(
+        delivery.status(),
+        MsgStatus::PartiallyDelivered {
+            received: 32,
+            total: 64,
+            ..
+        }
+    ));
+    assert_eq!(delivery.fragments(), 2);
+}
+
+#[test]
+fn unfragmented_message_too_big_for_a_queue_is_dropped() {
+    let sim: Sim = "1,msg,1,3,1,size=64\n100,endSim".parse().unwrap();
+    let result = Simulator::new(narrow_line(), sim).seed(1).run().unwrap();
+
+    let (_, delivery) = result.report(1).unwrap().deliveries().next().unwrap();
+    assert!(matches!(
+        delivery.status(),
+        MsgStatus::Dropped {
+            node: 2,
+            reason: DropReason::QueueFull,
+            ..
+        }
+    ));
+    assert_eq!(delivery.fragments(), 0);
+}
+
+/// Nodes 1 - 2 - 3 in a line, where 2 and 3 have no link back towards 1,
+/// so ACKs for messages from 1 never return.
+fn one_way_line() -> Ntwk {
+    NtwkBuilder::new()
+        .node(1, 1)
+        .link(1, 2)
+        .node(2, 1)
+        .link(2, 3)
+        .node(3, 1)
+        .link(3, 2)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn acked_message_is_sent_once() {
+    let sim: Sim = "2,msg,1,3,2,reliable,rto=10\n100,endSim".parse().unwrap();
+    let result = Simulator::new(one_way_line(), sim).seed(1).run().unwrap();
+
+    let ack = result.report(1).unwrap().ack().unwrap();
+    assert_eq!(ack.attempts(), 1);
+    assert_eq!(ack.rtt_samples(), [4]);
+    assert_eq!(ack.outcome(), AckOutcome::Acked { tick: 6 });
+}
+
+#[test]
+fn timeout_doubles_with_each_retransmission() {
+    // Sent at 1, then resent after 4 and 8 more ticks, and given up on
+    // 16 ticks after the last try
+    let sim: Sim = "1,msg,1,1,3,reliable,rto=4,retries=2\n100,endSim"
+        .parse()
+        .unwrap();
+    let result = Simulator::new(one_way_line(), sim).seed(1).run().unwrap();
+
+    let report = result.report(1).unwrap();
+    let ack = report.ack().unwrap();
+    assert_eq!(ack.attempts(), 3);
+    assert!(ack.rtt_samples().is_empty());
+    assert_eq!(
+        ack.outcome(),
+        AckOutcome::Failed {
+            tick: 1 + 4 + 8 + 16
+        }
+    );
+    // The first copy still got there
+    let (_, delivery) = report.deliveries().next().unwrap();
+    assert!(delivered(delivery.status()));
+}
+
+#[test]
+fn unreliable_message_has_no_ack_history() {
+    let sim: Sim = "1,msg,1,1,3\n100,endSim".parse().unwrap();
+    let result = Simulator::new(one_way_line(), sim).seed(1).run().unwrap();
+    assert!(result.report(1).unwrap().ack().is_none());
+}
+
+/// `line`, with every link losing a fifth of its packets and adding up to
+/// 3 ticks of jitter.
+fn noisy_line() -> Ntwk {
+    let mut builder = NtwkBuilder::new();
+    for (node, links) in [(1, &[2][..]), (2, &[1, 3]), (3, &[2])] {
+        builder.node(node, 1);
+        for &to in links {
+            builder
+                .link(node, to)
+                .attr("loss", "0.2")
+                .attr("jitter", "uniform:3");
+        }
+    }
+    builder.build().unwrap()
+}
+
+/// Messages both ways over `noisy_line`, some of them reliable, and
+/// Poisson traffic drawn from the run's seed, with a snapshot at t=20.
+fn noisy_sim(snapshot: &str) -> Sim {
+    format!(
+        "1,msg,1,1,3,reliable\n\
+         3,poisson,10,3,1,0.5,20\n\
+         5,msg,2,3,1,size=256\n\
+         12,bcast,3,2\n\
+         20,snapshot,{snapshot}\n\
+         22,msg,4,1,3,reliable,rto=6\n\
+         25,mcast,5,2,1,3\n\
+         200,endSim"
+    )
+    .parse()
+    .unwrap()
+}
+
+/// Every report of a run, as `rep` would print them.
+fn reports(result: &RunResult) -> Vec<String> {
+    result.reports().iter().map(|r| r.to_string()).collect()
+}
+
+fn snapshot_file(name: &str) -> String {
+    format!("{}/{name}", env!("CARGO_TARGET_TMPDIR"))
+}
+
+#[test]
+fn same_seed_gives_the_same_run() {
+    let snapshot = snapshot_file("same_seed.snap");
+    let run = |seed| {
+        Simulator::new(noisy_line(), noisy_sim(&snapshot))
+            .seed(seed)
+            .run()
+            .unwrap()
+    };
+    let first = run(7);
+    assert_eq!(first.seed(), 7);
+    assert_eq!(reports(&first), reports(&run(7)));
+    assert_ne!(reports(&first), reports(&run(8)));
+}
+
+#[test]
+fn resumed_run_matches_an_uninterrupted_one() {
+    let snapshot = snapshot_file("resume.snap");
+    let straight = Simulator::new(noisy_line(), noisy_sim(&snapshot))
+        .seed(7)
+        .run()
+        .unwrap();
+    let resumed = Simulator::resume(&snapshot, noisy_sim(&snapshot))
+        .unwrap()
+        .run()
+        .unwrap();
+
+    assert_eq!(resumed.seed(), straight.seed());
+    assert_eq!(resumed.end_tick(), straight.end_tick());
+    assert_eq!(reports(&resumed), reports(&straight));
+}
+
+#[test]
+fn copies_cut_by_different_mtus_reassemble_exactly() {
+    // A broadcast from 1 reaches 4 both through 2, in fragments of 32
+    // bytes, and through 3, in fragments of 16. The first from 2 arrives
+    // between those from 3, so the two sets overlap
+    let mut builder = NtwkBuilder::new();
+    builder.node(1, 1);
+    builder.link(1, 2).attr("mtu", "32").attr("bw", "16");
+    builder.link(1, 3).attr("mtu", "16").attr("bw", "16");
+    builder.node(2, 1).link(2, 1);
+    builder.link(2, 4).attr("mtu", "32").attr("bw", "16");
+    builder.node(3, 2).link(3, 1);
+    builder.link(3, 4).attr("mtu", "16").attr("bw", "16");
+    builder.node(4, 1).link(4, 2).link(4, 3);
+    let data = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ+-";
+    let sim: Sim = format!("1,bcast,1,1,data={data}\n200,endSim")
+        .parse()
+        .unwrap();
+    let result = Simulator::new(builder.build().unwrap(), sim)
+        .seed(1)
+        .run()
+        .unwrap();
+
+    for (node, delivery) in result.report(1).unwrap().deliveries() {
+        assert!(delivered(delivery.status()), "node {node}");
+        assert_eq!(delivery.payload(), Some(data.as_bytes()), "node {node}");
+    }
+}
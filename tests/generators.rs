@@ -0,0 +1,18 @@
+//! Limits on the networks and workloads the generators build.
+
+use guwah::{ArrivalProcess, ErrCode, Ntwk, QueueDelays, Sim, Topology, Workload};
+
+#[test]
+fn workload_of_too_many_messages_is_an_error() {
+    let ntwk = Ntwk::generate(Topology::Star { nodes: 5 }, QueueDelays::default(), 1).unwrap();
+    let flood = Workload::new(ArrivalProcess::Poisson { rate: 1e9 }, 5);
+    assert!(matches!(
+        Sim::generate(&flood, &ntwk, 1),
+        Err(ErrCode::BadScenario(_))
+    ));
+    let periodic = Workload::new(ArrivalProcess::Periodic { interval: 2 }, 2_000_001);
+    assert!(matches!(
+        Sim::generate(&periodic, &ntwk, 1),
+        Err(ErrCode::BadScenario(_))
+    ));
+}
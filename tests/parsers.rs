@@ -0,0 +1,136 @@
+//! Whitespace, line ending and comment handling in the `.ntwk` and `.sim`
+//! parsers. Each file in `tests/corpus` holds the same contents as a clean
+//! file and has to read back equal to it.
+
+use guwah::{ErrCode, Ntwk, Sim};
+
+fn corpus(name: &str) -> String {
+    format!("{}/tests/corpus/{name}", env!("CARGO_MANIFEST_DIR"))
+}
+
+fn basic_ntwk() -> Ntwk {
+    Ntwk::from_file(&format!("{}/config/basic.ntwk", env!("CARGO_MANIFEST_DIR"))).unwrap()
+}
+
+fn basic_sim() -> Sim {
+    Sim::from_file(&format!("{}/config/basic.sim", env!("CARGO_MANIFEST_DIR"))).unwrap()
+}
+
+/// What a sim reads as once its bad lines are skipped.
+fn end_only() -> Sim {
+    "5,endSim".parse().unwrap()
+}
+
+#[test]
+fn ntwk_with_messy_whitespace_and_comments() {
+    assert_eq!(
+        Ntwk::from_file(&corpus("messy.ntwk")).unwrap(),
+        basic_ntwk()
+    );
+}
+
+#[test]
+fn ntwk_with_crlf_line_endings() {
+    assert_eq!(Ntwk::from_file(&corpus("crlf.ntwk")).unwrap(), basic_ntwk());
+}
+
+#[test]
+fn sim_with_messy_whitespace_and_comments() {
+    assert_eq!(Sim::from_file(&corpus("messy.sim")).unwrap(), basic_sim());
+}
+
+#[test]
+fn sim_with_crlf_line_endings() {
+    assert_eq!(Sim::from_file(&corpus("crlf.sim")).unwrap(), basic_sim());
+}
+
+#[test]
+fn link_attrs_with_whitespace() {
+    let messy: Ntwk = "1, 1, 2, 8\r\n 2 , bw = 16 ,\tloss=0.5 # lossy\r\nendNode\r\n\
+                       2,1,2\n1 ,jitter = uniform:3\nendNode\nendNet"
+        .parse()
+        .unwrap();
+    let clean: Ntwk =
+        "1,1,2,8\n2,bw=16,loss=0.5\nendNode\n2,1,2\n1,jitter=uniform:3\nendNode\nendNet"
+            .parse()
+            .unwrap();
+    assert_eq!(messy, clean);
+}
+
+#[test]
+fn instr_options_with_whitespace() {
+    let messy: Sim = " 0 , seed , 7\n\
+                      1, msg, 1, 1, 2, size = 64, data = hi there , reliable\r\n\
+                      2,\tmcast, 2, 1, 2, 3\t,size=8\n\
+                      3, flow, 3, 1, 2, 5, 4   # four messages\n\
+                      4, poisson, 10, 2, 1, 0.5, 3, 9\n\
+                      5, snapshot,  snap.txt  \n\
+                      6, rep, 1\n\
+                      7, endSim \n"
+        .parse()
+        .unwrap();
+    let clean: Sim = "0,seed,7\n\
+                      1,msg,1,1,2,size=64,data=hi there,reliable\n\
+                      2,mcast,2,1,2,3,size=8\n\
+                      3,flow,3,1,2,5,4\n\
+                      4,poisson,10,2,1,0.5,3,9\n\
+                      5,snapshot,snap.txt\n\
+                      6,rep,1\n\
+                      7,endSim\n"
+        .parse()
+        .unwrap();
+    assert_eq!(messy, clean);
+}
+
+#[test]
+fn sim_of_only_blank_and_comment_lines_is_empty() {
+    assert!(matches!(
+        " \t\r\n\n  # nothing here\r\n".parse::<Sim>(),
+        Err(ErrCode::SimFileParseFailed { reason, .. }) if reason == "no instructions"
+    ));
+}
+
+#[test]
+fn whitespace_inside_a_field_is_still_bad() {
+    assert!(matches!(
+        "1 2,1,3\n2\nendNode\nendNet".parse::<Ntwk>(),
+        Err(ErrCode::NtwkFileParseFailed { .. })
+    ));
+    assert_eq!("3,msg,1,4 1\n5,endSim".parse::<Sim>().unwrap(), end_only());
+}
+
+#[test]
+fn bad_instructions_are_skipped() {
+    assert_eq!("3,msg,1,4\n5,endSim".parse::<Sim>().unwrap(), end_only());
+    assert_eq!("3,mesg,1,4,1\n5,endSim".parse::<Sim>().unwrap(), end_only());
+}
+
+#[test]
+fn flows_with_ids_past_the_largest_are_skipped() {
+    assert_eq!(
+        "3,flow,4294967290,1,2,1,7\n5,endSim"
+            .parse::<Sim>()
+            .unwrap(),
+        end_only()
+    );
+    assert_ne!(
+        "3,flow,4294967290,1,2,1,6\n5,endSim"
+            .parse::<Sim>()
+            .unwrap(),
+        end_only()
+    );
+}
+
+#[test]
+fn ntwk_errors_name_the_bad_part() {
+    let reason = |ntwk: &str| match ntwk.parse::<Ntwk>() {
+        Err(ErrCode::NtwkFileParseFailed { reason, .. }) => reason,
+        other => panic!("{other:?}"),
+    };
+    assert_eq!(
+        reason("1,1,1\n2,bw=0\nendNode\nendNet"),
+        "bad link attribute: bw=0"
+    );
+    assert!(reason("1,1,1\nx\nendNode\nendNet").starts_with("malformed line: x ("));
+    assert!(reason("1,1\n2\nendNode\nendNet").starts_with("malformed line: 1,1 ("));
+}
@@ -0,0 +1,73 @@
+//! Networks and sims written back out by `write_ntwk` and `write_sim` have
+//! to read back the same as they were.
+
+use guwah::{Ntwk, Routing, Sim};
+
+fn ntwk_round_trip(ntwk: &Ntwk) -> Ntwk {
+    let mut text = Vec::new();
+    ntwk.write_ntwk(&mut text).unwrap();
+    Ntwk::from_reader(text.as_slice()).unwrap()
+}
+
+fn sim_round_trip(sim: &Sim) -> Sim {
+    let mut text = Vec::new();
+    sim.write_sim(&mut text).unwrap();
+    Sim::from_reader(text.as_slice()).unwrap()
+}
+
+#[test]
+fn ntwk_with_every_link_attr() {
+    let ntwk: Ntwk = "1,2,1,8\n\
+                      2,bw=16,mtu=32\n\
+                      3,loss=0.25,jitter=uniform:3\n\
+                      endNode\n\
+                      2,1,2\n\
+                      1,jitter=normal:1.5\n\
+                      endNode\n\
+                      3,1,1\n\
+                      1,bw=4,mtu=8,loss=0.5,jitter=uniform:0\n\
+                      endNode\n\
+                      endNet"
+        .parse()
+        .unwrap();
+    assert_eq!(ntwk_round_trip(&ntwk), ntwk);
+}
+
+#[test]
+fn ntwk_routed_by_delay_reads_back_routed_by_hops() {
+    // Both ways from 1 to 4 take two hops, but the one through 2 is slow
+    let mut ntwk: Ntwk = "1,2,1\n2\n3\nendNode\n\
+                          2,2,9\n1\n4\nendNode\n\
+                          3,2,1\n1\n4\nendNode\n\
+                          4,2,1\n2\n3\nendNode\n\
+                          endNet"
+        .parse()
+        .unwrap();
+    let by_hops = ntwk_round_trip(&ntwk);
+    ntwk.set_routing(Routing::Delay);
+    assert_ne!(ntwk, by_hops);
+
+    let mut read_back = ntwk_round_trip(&ntwk);
+    assert_eq!(read_back, by_hops);
+    read_back.set_routing(Routing::Delay);
+    assert_eq!(read_back, ntwk);
+}
+
+#[test]
+fn sim_with_every_instr_kind() {
+    let sim: Sim = "0,seed,7\n\
+                    1,msg,1,1,2\n\
+                    1,msg,2,1,3,size=64,data=hi there,reliable,rto=4,retries=2\n\
+                    2,bcast,3,2,size=16\n\
+                    2,mcast,4,1,2,3,data=both\n\
+                    3,flow,10,1,2,5,4,size=8,reliable\n\
+                    4,poisson,20,2,1,0.5,3\n\
+                    4,poisson,30,2,3,0.25,2,9,data=x\n\
+                    5,snapshot,snap.txt\n\
+                    6,rep,1\n\
+                    6,rep\n\
+                    7,endSim\n"
+        .parse()
+        .unwrap();
+    assert_eq!(sim_round_trip(&sim), sim);
+}